@@ -0,0 +1,320 @@
+//! Word segmentation by dictionary for scripts written without spaces between words
+//! (Thai, Lao, Khmer, Myanmar: line break class SA of UAX #14).
+
+use std::collections::HashSet;
+#[cfg(any(
+    feature = "thai",
+    feature = "lao",
+    feature = "khmer",
+    feature = "myanmar"
+))]
+use std::sync::OnceLock;
+use unicode_linebreak::{break_property, BreakClass};
+use unicode_segmentation::UnicodeSegmentation;
+
+#[cfg(feature = "thai")]
+static THAI: OnceLock<Dictionary> = OnceLock::new();
+#[cfg(feature = "lao")]
+static LAO: OnceLock<Dictionary> = OnceLock::new();
+#[cfg(feature = "khmer")]
+static KHMER: OnceLock<Dictionary> = OnceLock::new();
+#[cfg(feature = "myanmar")]
+static MYANMAR: OnceLock<Dictionary> = OnceLock::new();
+
+/// A word list that splits runs of Thai, Lao, Khmer or Myanmar text into words, lines may
+/// break between them.
+///
+/// The word lists of ICU for these scripts are embedded with the `thai`, `lao`, `khmer` and
+/// `myanmar` features, of which only `thai` is on by default. Without the feature of its
+/// script and without a dictionary of its own, a run does not break inside.
+///
+/// A run is split into the fewest words, characters that start no word of the list stay
+/// with the word before them.
+#[derive(Debug, Clone, Default)]
+pub struct Dictionary {
+    words: HashSet<String>,
+    /// longest word, in chars
+    max_word_len: usize,
+}
+
+impl Dictionary {
+    /// Load a word list, words are separated by whitespace and lines starting with `#` are
+    /// comments.
+    pub fn from_words(words: &str) -> Dictionary {
+        let mut dictionary = Dictionary::default();
+        for word in words
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .flat_map(str::split_whitespace)
+        {
+            dictionary.max_word_len = dictionary.max_word_len.max(word.chars().count());
+            dictionary.words.insert(word.to_string());
+        }
+        dictionary
+    }
+
+    /// Load an embedded word list, whose lines are front coded: the number of leading chars
+    /// shared with the word before, followed by the rest of the word. A letter is one byte,
+    /// `0x80 + n` for the code point `base + n` and `0x40 + n` for `base + 0x80 + n`.
+    #[cfg(any(
+        feature = "thai",
+        feature = "lao",
+        feature = "khmer",
+        feature = "myanmar"
+    ))]
+    fn from_front_coded(bytes: &[u8], base: u32) -> Dictionary {
+        let letter = |byte: u8| {
+            let n = match byte {
+                0x80.. => u32::from(byte) - 0x80,
+                _ => u32::from(byte) - 0x40 + 0x80,
+            };
+            char::from_u32(base + n).unwrap_or(char::REPLACEMENT_CHARACTER)
+        };
+
+        let mut dictionary = Dictionary::default();
+        let mut word = String::new();
+        for line in bytes
+            .split(|&byte| byte == b'\n')
+            .filter(|line| !line.is_empty() && !line.starts_with(b"#"))
+        {
+            let digits = line.iter().take_while(|v| v.is_ascii_digit()).count();
+            let shared = line[..digits]
+                .iter()
+                .fold(0, |shared, v| shared * 10 + usize::from(v - b'0'));
+            let prefix_len = word.char_indices().nth(shared).map_or(word.len(), |v| v.0);
+            word.truncate(prefix_len);
+            word.extend(line[digits..].iter().map(|&v| letter(v)));
+
+            dictionary.max_word_len = dictionary.max_word_len.max(word.chars().count());
+            dictionary.words.insert(word.clone());
+        }
+        dictionary
+    }
+
+    /// The embedded Thai words.
+    #[cfg(feature = "thai")]
+    pub fn thai() -> &'static Dictionary {
+        THAI.get_or_init(|| {
+            Dictionary::from_front_coded(include_bytes!("dictionaries/thai.dic"), 0x0E00)
+        })
+    }
+
+    /// The embedded Lao words.
+    #[cfg(feature = "lao")]
+    pub fn lao() -> &'static Dictionary {
+        LAO.get_or_init(|| {
+            Dictionary::from_front_coded(include_bytes!("dictionaries/lao.dic"), 0x0E80)
+        })
+    }
+
+    /// The embedded Khmer words.
+    #[cfg(feature = "khmer")]
+    pub fn khmer() -> &'static Dictionary {
+        KHMER.get_or_init(|| {
+            Dictionary::from_front_coded(include_bytes!("dictionaries/khmer.dic"), 0x1780)
+        })
+    }
+
+    /// The embedded Burmese words.
+    #[cfg(feature = "myanmar")]
+    pub fn myanmar() -> &'static Dictionary {
+        MYANMAR.get_or_init(|| {
+            Dictionary::from_front_coded(include_bytes!("dictionaries/myanmar.dic"), 0x1000)
+        })
+    }
+
+    /// The embedded dictionary of the script of `ch`, if its feature is on.
+    fn embedded(ch: char) -> Option<&'static Dictionary> {
+        match ch as u32 {
+            #[cfg(feature = "thai")]
+            0x0E00..=0x0E7F => Some(Dictionary::thai()),
+            #[cfg(feature = "lao")]
+            0x0E80..=0x0EFF => Some(Dictionary::lao()),
+            #[cfg(feature = "myanmar")]
+            0x1000..=0x109F | 0xA9E0..=0xA9FF | 0xAA60..=0xAA7F => Some(Dictionary::myanmar()),
+            #[cfg(feature = "khmer")]
+            0x1780..=0x17FF | 0x19E0..=0x19FF => Some(Dictionary::khmer()),
+            _ => None,
+        }
+    }
+
+    /// Byte offsets in `text` where a word other than the first one starts, in increasing
+    /// order.
+    pub fn segment(&self, text: &str) -> Vec<usize> {
+        // words start and end at grapheme boundaries, so marks stay with their base letter
+        let mut bounds: Vec<usize> = text.grapheme_indices(true).map(|v| v.0).collect();
+        bounds.push(text.len());
+        let n = bounds.len() - 1;
+
+        // best (unknown graphemes, words) up to each boundary, and where its last word starts
+        let mut best: Vec<Option<(usize, usize)>> = vec![None; n + 1];
+        let mut from = vec![(0, false); n + 1];
+        best[0] = Some((0, 0));
+
+        for i in 0..n {
+            let Some((unknown, words)) = best[i] else {
+                continue;
+            };
+            let mut relax = |j: usize, cost: (usize, usize), known: bool| {
+                if best[j].is_none_or(|v| cost < v) {
+                    best[j] = Some(cost);
+                    from[j] = (i, known);
+                }
+            };
+
+            relax(i + 1, (unknown + 1, words), false);
+            for j in i + 1..=n.min(i + self.max_word_len) {
+                if self.words.contains(&text[bounds[i]..bounds[j]]) {
+                    relax(j, (unknown, words + 1), true);
+                }
+            }
+        }
+
+        // unknown graphemes are kept with the word before them
+        let mut breaks = vec![];
+        let mut j = n;
+        while j > 0 {
+            let (i, known) = from[j];
+            if known && i > 0 {
+                breaks.push(bounds[i]);
+            }
+            j = i;
+        }
+        breaks.reverse();
+        breaks
+    }
+}
+
+/// Break class SA: letters and marks of scripts that need a dictionary to find words.
+pub fn is_complex_context(ch: char) -> bool {
+    break_property(ch as u32) == BreakClass::ComplexContext
+}
+
+/// The run of SA characters starting at `start` and the word boundaries inside it, found by
+/// `dictionary` or else the embedded dictionary of the script. Returns the end of the run and
+/// the byte offsets of the boundaries.
+pub fn run_breaks(
+    text: &str,
+    start: usize,
+    dictionary: Option<&Dictionary>,
+) -> (usize, Vec<usize>) {
+    let embedded = text[start..].chars().next().and_then(Dictionary::embedded);
+    // without a dictionary of its own, a run ends where the script changes
+    let in_run = |ch: char| {
+        is_complex_context(ch)
+            && (dictionary.is_some()
+                || match (Dictionary::embedded(ch), embedded) {
+                    (Some(a), Some(b)) => std::ptr::eq(a, b),
+                    (a, b) => a.is_none() && b.is_none(),
+                })
+    };
+
+    let end = text[start..]
+        .char_indices()
+        .find(|&(_, ch)| !in_run(ch))
+        .map_or(text.len(), |(i, _)| start + i);
+    let breaks = dictionary.or(embedded).map_or(vec![], |dictionary| {
+        dictionary
+            .segment(&text[start..end])
+            .into_iter()
+            .map(|v| start + v)
+            .collect()
+    });
+    (end, breaks)
+}
+
+/// Word boundaries inside all runs of SA characters of `text`.
+pub fn dictionary_breaks(text: &str, dictionary: Option<&Dictionary>) -> Vec<usize> {
+    let mut breaks = vec![];
+    let mut pos = 0;
+    while let Some((offset, _)) = text[pos..]
+        .char_indices()
+        .find(|&(_, ch)| is_complex_context(ch))
+    {
+        let (end, run) = run_breaks(text, pos + offset, dictionary);
+        breaks.extend(run);
+        pos = end;
+    }
+    breaks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(dictionary: &Dictionary, text: &str) -> Vec<String> {
+        let mut words = vec![];
+        let mut start = 0;
+        for pos in dictionary.segment(text).into_iter().chain([text.len()]) {
+            words.push(text[start..pos].to_string());
+            start = pos;
+        }
+        words
+    }
+
+    #[test]
+    #[cfg(feature = "thai")]
+    fn test_embedded_thai() {
+        assert_eq!(
+            words(Dictionary::thai(), "สินค้าคุณภาพดีราคาถูก"),
+            ["สินค้า", "คุณภาพ", "ดี", "ราคา", "ถูก"]
+        );
+        assert_eq!(words(Dictionary::thai(), ""), [""]);
+    }
+
+    #[test]
+    #[cfg(feature = "lao")]
+    fn test_embedded_lao() {
+        assert_eq!(
+            words(Dictionary::lao(), "ພາສາລາວເປັນພາສາທີ່ສວຍງາມ"),
+            ["ພາສາ", "ລາວ", "ເປັນ", "ພາສາ", "ທີ່", "ສວຍງາມ"]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "khmer")]
+    fn test_embedded_khmer() {
+        assert_eq!(
+            words(Dictionary::khmer(), "ភាសាខ្មែរគឺជាភាសាផ្លូវការ"),
+            ["ភាសាខ្មែរ", "គឺជា", "ភាសាផ្លូវការ"]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "myanmar")]
+    fn test_embedded_myanmar() {
+        assert_eq!(
+            words(Dictionary::myanmar(), "မြန်မာနိုင်ငံ၏ရုံးသုံးဘာသာစကားဖြစ်သည်"),
+            ["မြန်မာနိုင်ငံ", "၏", "ရုံးသုံး", "ဘာသာစကား", "ဖြစ်သည်"]
+        );
+    }
+
+    #[test]
+    fn test_from_words() {
+        let dictionary = Dictionary::from_words("# comment\nกา ไก่\nขา\n");
+        assert_eq!(words(&dictionary, "กาไก่ขา"), ["กา", "ไก่", "ขา"]);
+        // unknown characters stay with the word before them
+        assert_eq!(words(&dictionary, "กาๆไก่ฯขา"), ["กาๆ", "ไก่ฯ", "ขา"]);
+        assert_eq!(words(&dictionary, "ๆกาข"), ["ๆ", "กาข"]);
+    }
+
+    #[test]
+    fn test_dictionary_breaks() {
+        let text = "abc ไทยภาษา, ພາສາລາວ";
+        #[cfg(all(feature = "thai", feature = "lao"))]
+        {
+            assert_eq!(dictionary_breaks(text, None), [13, 39]);
+            assert_eq!(run_breaks(text, 4, None), (25, vec![13]));
+        }
+        #[cfg(all(feature = "thai", not(feature = "lao")))]
+        assert_eq!(dictionary_breaks(text, None), [13]);
+        // without the embedded word lists a run does not break
+        #[cfg(not(any(feature = "thai", feature = "lao")))]
+        assert_eq!(dictionary_breaks(text, None), []);
+
+        // a dictionary of its own is used for all scripts
+        let dictionary = Dictionary::from_words("ไทย");
+        assert_eq!(dictionary_breaks(text, Some(&dictionary)), []);
+        assert_eq!(dictionary_breaks("ไทยไทย", Some(&dictionary)), [9]);
+    }
+}
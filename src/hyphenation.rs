@@ -0,0 +1,203 @@
+//! Liang's hyphenation algorithm, as used by TeX, over `hyph-*.tex` pattern files.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+static EN_US: OnceLock<Hyphenator> = OnceLock::new();
+
+/// Finds the places where a word may be hyphenated.
+///
+/// Patterns are read from TeX pattern files such as the `hyph-*.tex` files of the
+/// [hyph-utf8](https://github.com/hyphenation/tex-hyphen) project: the contents of
+/// `\patterns{...}` and `\hyphenation{...}`, everything else is ignored.
+#[derive(Debug, Clone, Default)]
+pub struct Hyphenator {
+    /// letters of a pattern -> its levels, one more than there are letters
+    patterns: HashMap<String, Vec<u8>>,
+    /// lowercase word -> char indices of its hyphens
+    exceptions: HashMap<String, Vec<usize>>,
+    /// longest pattern, in chars
+    max_pattern_len: usize,
+    left_min: usize,
+    right_min: usize,
+}
+
+impl Hyphenator {
+    /// Load the patterns and exceptions of a TeX pattern file.
+    ///
+    /// At least 2 characters are kept before a hyphen and 3 after it, use
+    /// [`Hyphenator::with_min`] to follow the conventions of the language.
+    pub fn from_tex(tex: &str) -> Hyphenator {
+        let mut hyphenator = Hyphenator {
+            left_min: 2,
+            right_min: 3,
+            ..Hyphenator::default()
+        };
+
+        let tex = tex
+            .lines()
+            .map(|line| line.split('%').next().unwrap_or_default())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let mut rest = tex.as_str();
+        while let Some(pos) = rest.find('\\') {
+            rest = &rest[pos + 1..];
+            let is_patterns = rest.starts_with("patterns{");
+            if !is_patterns && !rest.starts_with("hyphenation{") {
+                continue;
+            }
+
+            rest = &rest[rest.find('{').unwrap() + 1..];
+            let body_len = rest.find('}').unwrap_or(rest.len());
+            for token in rest[..body_len].split_whitespace() {
+                if is_patterns {
+                    hyphenator.add_pattern(token);
+                } else {
+                    hyphenator.add_exception(token);
+                }
+            }
+            rest = &rest[body_len..];
+        }
+
+        hyphenator
+    }
+
+    /// The embedded American English patterns.
+    pub fn en_us() -> &'static Hyphenator {
+        EN_US.get_or_init(|| Hyphenator::from_tex(include_str!("patterns/hyph-en-us.tex")))
+    }
+
+    /// Minimum number of characters before and after a hyphen.
+    pub fn with_min(mut self, left_min: usize, right_min: usize) -> Self {
+        self.left_min = left_min.max(1);
+        self.right_min = right_min.max(1);
+        self
+    }
+
+    /// A pattern such as `.ach4` or `hy3ph`: letters with the levels between them.
+    fn add_pattern(&mut self, pattern: &str) {
+        let mut letters = String::new();
+        let mut levels = vec![0];
+        for ch in pattern.chars() {
+            match ch.to_digit(10) {
+                Some(level) => *levels.last_mut().unwrap() = level as u8,
+                None => {
+                    letters.push(ch);
+                    levels.push(0);
+                }
+            }
+        }
+
+        self.max_pattern_len = self.max_pattern_len.max(levels.len() - 1);
+        self.patterns.insert(letters, levels);
+    }
+
+    /// A word with its hyphens spelled out, such as `ta-ble`.
+    fn add_exception(&mut self, exception: &str) {
+        let mut word = String::new();
+        let mut hyphens = vec![];
+        for ch in exception.chars() {
+            if ch == '-' {
+                hyphens.push(word.chars().count());
+            } else {
+                word.push(ch);
+            }
+        }
+        self.exceptions.insert(word.to_lowercase(), hyphens);
+    }
+
+    /// Byte offsets in `word` where it may be hyphenated, in increasing order.
+    pub fn hyphenate(&self, word: &str) -> Vec<usize> {
+        let chars: Vec<char> = word.chars().map(to_lower).collect();
+        let n = chars.len();
+        if n < self.left_min + self.right_min {
+            return vec![];
+        }
+
+        let lowercase: String = chars.iter().collect();
+        let hyphens = match self.exceptions.get(&lowercase) {
+            Some(hyphens) => hyphens.clone(),
+            None => self.pattern_hyphens(&chars),
+        };
+
+        let offsets: Vec<usize> = word.char_indices().map(|(i, _)| i).collect();
+        hyphens
+            .into_iter()
+            .filter(|&i| i >= self.left_min && n - i >= self.right_min)
+            .map(|i| offsets[i])
+            .collect()
+    }
+
+    /// Char indices of the hyphens found by the patterns, `chars` already lowercase.
+    fn pattern_hyphens(&self, chars: &[char]) -> Vec<usize> {
+        let dotted: Vec<char> = [&['.'], chars, &['.']].concat();
+        let mut levels = vec![0; dotted.len() + 1];
+
+        let mut key = String::new();
+        for i in 0..dotted.len() {
+            key.clear();
+            for &ch in dotted[i..].iter().take(self.max_pattern_len) {
+                key.push(ch);
+                if let Some(pattern) = self.patterns.get(&key) {
+                    for (k, &level) in pattern.iter().enumerate() {
+                        levels[i + k] = levels[i + k].max(level);
+                    }
+                }
+            }
+        }
+
+        // levels[i + 1] sits before chars[i], odd levels allow a hyphen
+        (1..chars.len())
+            .filter(|&i| levels[i + 1] % 2 == 1)
+            .collect()
+    }
+}
+
+/// Patterns are lowercase, characters without a single lowercase form are kept.
+fn to_lower(ch: char) -> char {
+    let mut lower = ch.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(lower), None) => lower,
+        _ => ch,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hyphenated(hyphenator: &Hyphenator, word: &str) -> String {
+        let mut result = String::new();
+        let mut start = 0;
+        for pos in hyphenator.hyphenate(word) {
+            result.push_str(&word[start..pos]);
+            result.push('-');
+            start = pos;
+        }
+        result.push_str(&word[start..]);
+        result
+    }
+
+    #[test]
+    fn test_en_us() {
+        let en_us = Hyphenator::en_us();
+        assert_eq!(hyphenated(en_us, "hyphenation"), "hy-phen-ation");
+        assert_eq!(hyphenated(en_us, "extensive"), "ex-ten-sive");
+        assert_eq!(hyphenated(en_us, "Probability"), "Prob-a-bil-ity");
+        assert_eq!(hyphenated(en_us, "recognize"), "rec-og-nize");
+        assert_eq!(hyphenated(en_us, "table"), "ta-ble");
+        assert_eq!(hyphenated(en_us, "project"), "project");
+        assert_eq!(hyphenated(en_us, "the"), "the");
+        assert_eq!(hyphenated(en_us, ""), "");
+    }
+
+    #[test]
+    fn test_from_tex() {
+        let tex = "% comment\n\\patterns{ % inline\n1ä 1b\n}\n\\hyphenation{Ab-ba}";
+        let hyphenator = Hyphenator::from_tex(tex).with_min(1, 1);
+        assert_eq!(hyphenated(&hyphenator, "aäbä"), "a-ä-b-ä");
+        assert_eq!(hyphenated(&hyphenator, "abba"), "ab-ba");
+        assert_eq!(hyphenated(&hyphenator.with_min(2, 2), "aäbä"), "aä-bä");
+    }
+}
@@ -1,15 +1,35 @@
-use crate::line::{Line, LineInfo};
+use crate::line::Line;
 
+mod dictionary;
+mod hyphenation;
 mod line;
+mod link;
+mod measure;
+mod opportunity;
+mod paragraph;
+mod tab;
+mod uax14;
 mod word;
 
 pub mod tests;
 
+pub use crate::dictionary::Dictionary;
+pub use crate::hyphenation::Hyphenator;
+pub use crate::line::{
+    BreakEngine, LineBreakStrictness, LineInfo, LinePosition, LineTerminator, WhiteSpace,
+    WordBreak, WrapMode,
+};
+pub use crate::link::{Link, LinkKind};
+pub use crate::measure::{AmbiguousWidth, MonospaceMeasurer, WidthMeasurer};
+pub use crate::opportunity::{BreakInfo, BreakKind, BreakOpportunities};
+pub use crate::paragraph::{HangingPunctuation, OrphanControl, SpacingTrim};
+pub use crate::tab::{TabAlign, TabStop, TabStops};
+
 #[allow(dead_code)]
 struct TextFlowContext {}
 
 #[allow(dead_code)]
-pub struct TextFlow<'a> {
+pub struct TextFlow<'a, M: WidthMeasurer = MonospaceMeasurer> {
     text: &'a str,
     max_width: usize,
     line_height: usize,
@@ -19,11 +39,11 @@ pub struct TextFlow<'a> {
 
     context: TextFlowContext,
 
-    lines: Line<'a>,
+    lines: Line<'a, M>,
 }
 
 impl TextFlow<'_> {
-    pub fn new(text: &str, max_width: usize) -> TextFlow {
+    pub fn new(text: &str, max_width: usize) -> TextFlow<'_> {
         let mut flow = TextFlow {
             text,
             max_width,
@@ -41,7 +61,141 @@ impl TextFlow<'_> {
     }
 }
 
-impl Iterator for TextFlow<'_> {
+impl<'a> TextFlow<'a> {
+    /// Width of East Asian Ambiguous characters, `Narrow` (1 cell) by default.
+    pub fn with_ambiguous_width(self, ambiguous_width: AmbiguousWidth) -> TextFlow<'a> {
+        self.with_measurer(MonospaceMeasurer::new(ambiguous_width))
+    }
+}
+
+impl<'a, M: WidthMeasurer> TextFlow<'a, M> {
+    /// A tab advances to the next multiple of `tab_width` columns from the line start, as
+    /// `expand(1)` does with 8. It is 0 by default, where a tab only advances to the tab stops.
+    pub fn with_tab_width(mut self, tab_width: usize) -> Self {
+        self.tab_width = tab_width;
+        self.lines = self.lines.with_tab_width(tab_width);
+        self
+    }
+
+    /// Explicit tab stops, e.g. `TabStops::at([8, 20, 32])`, counted from the start of the
+    /// line. Tabs past the last one advance by `tab_width`.
+    pub fn with_tab_stops(mut self, tab_stops: TabStops) -> Self {
+        self.lines = self.lines.with_tab_stops(tab_stops);
+        self
+    }
+
+    /// Choose how break opportunities are found, [`BreakEngine::Heuristic`] by default.
+    pub fn with_break_engine(mut self, engine: BreakEngine) -> Self {
+        self.lines = self.lines.with_break_engine(engine);
+        self
+    }
+
+    /// Where lines may break inside words, [`WordBreak::Normal`] by default.
+    pub fn with_word_break(mut self, word_break: WordBreak) -> Self {
+        self.lines = self.lines.with_word_break(word_break);
+        self
+    }
+
+    /// Kinsoku rules for CJK text, [`LineBreakStrictness::Normal`] by default.
+    pub fn with_line_break_strictness(mut self, strictness: LineBreakStrictness) -> Self {
+        self.lines = self.lines.with_line_break_strictness(strictness);
+        self
+    }
+
+    /// Choose how the breaks of a paragraph are picked, [`WrapMode::Greedy`] by default.
+    pub fn with_wrap_mode(mut self, wrap_mode: WrapMode) -> Self {
+        self.lines = self.lines.with_wrap_mode(wrap_mode);
+        self
+    }
+
+    /// What the last line of a paragraph needs in [`WrapMode::Pretty`].
+    pub fn with_orphan_control(mut self, orphan_control: OrphanControl) -> Self {
+        self.lines = self.lines.with_orphan_control(orphan_control);
+        self
+    }
+
+    /// Whether white space collapses, line breaks are kept and lines wrap,
+    /// [`WhiteSpace::PreWrap`] by default.
+    pub fn with_white_space(mut self, white_space: WhiteSpace) -> Self {
+        self.lines = self.lines.with_white_space(white_space);
+        self
+    }
+
+    /// Punctuation that may hang outside `max_width`, nothing hangs by default.
+    pub fn with_hanging_punctuation(mut self, hanging_punctuation: HangingPunctuation) -> Self {
+        self.lines = self.lines.with_hanging_punctuation(hanging_punctuation);
+        self
+    }
+
+    /// Set fullwidth CJK brackets and stops half width next to each other and at line ends,
+    /// none is trimmed by default.
+    pub fn with_spacing_trim(mut self, spacing_trim: SpacingTrim) -> Self {
+        self.lines = self.lines.with_spacing_trim(spacing_trim);
+        self
+    }
+
+    /// Put a gap of width `gap` between ideographs and Latin letters or digits next to them,
+    /// no gap by default.
+    pub fn with_autospace(mut self, gap: usize) -> Self {
+        self.lines = self.lines.with_autospace(gap);
+        self
+    }
+
+    /// Hyphenate words at the points found by `hyphenator`, e.g. [`Hyphenator::en_us`].
+    pub fn with_hyphenator(mut self, hyphenator: &'a Hyphenator) -> Self {
+        self.lines = self.lines.with_hyphenator(hyphenator);
+        self
+    }
+
+    /// Find the words of Thai, Lao, Khmer and Myanmar text with `dictionary` instead of the
+    /// embedded word lists, e.g. one made with [`Dictionary::from_words`].
+    ///
+    /// Only the Thai word list is embedded by default. Lao, Khmer and Myanmar text breaks
+    /// between words with the `lao`, `khmer` and `myanmar` cargo features or with a
+    /// dictionary passed here; without either, a run of it never breaks inside.
+    pub fn with_dictionary(mut self, dictionary: &'a Dictionary) -> Self {
+        self.lines = self.lines.with_dictionary(dictionary);
+        self
+    }
+
+    /// Detect URLs, e-mail addresses and file paths, which then break at their separators
+    /// instead of at the punctuation rules of prose and are reported in
+    /// [`LineInfo::links`]. Off by default.
+    pub fn with_link_detection(mut self, detect_links: bool) -> Self {
+        self.lines = self.lines.with_link_detection(detect_links);
+        self
+    }
+
+    /// Never break inside the host of a URL or the domain of an e-mail address, lines still
+    /// break at the `/`, `?`, `&` and `#` after it. Only applies with
+    /// [`TextFlow::with_link_detection`].
+    pub fn with_keep_link_hosts(mut self, keep_hosts: bool) -> Self {
+        self.lines = self.lines.with_keep_link_hosts(keep_hosts);
+        self
+    }
+
+    /// The break opportunities of the text under these settings, in place of its lines:
+    /// `max_width` is not used and long words are not cut.
+    pub fn break_opportunities(self) -> BreakOpportunities<'a, M> {
+        BreakOpportunities::from_line(self.lines)
+    }
+
+    /// Replace the width measurer, e.g. with one backed by real glyph advances.
+    pub fn with_measurer<N: WidthMeasurer>(self, measurer: N) -> TextFlow<'a, N> {
+        TextFlow {
+            text: self.text,
+            max_width: self.max_width,
+            line_height: self.line_height,
+            line_spacing: self.line_spacing,
+            word_spacing: self.word_spacing,
+            tab_width: self.tab_width,
+            context: self.context,
+            lines: self.lines.with_measurer(measurer),
+        }
+    }
+}
+
+impl<M: WidthMeasurer> Iterator for TextFlow<'_, M> {
     type Item = LineInfo;
 
     fn next(&mut self) -> Option<Self::Item> {
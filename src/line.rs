@@ -1,13 +1,88 @@
-use crate::word::{Word, WordInfo, WordType};
+use crate::dictionary::Dictionary;
+use crate::hyphenation::Hyphenator;
+use crate::link::{Link, TextLinks};
+use crate::measure::{MonospaceMeasurer, WidthMeasurer};
+use crate::opportunity::{BreakInfo, BreakKind};
+use crate::paragraph::{self, HangingPunctuation, OrphanControl, Segment, SpacingTrim};
+use crate::tab::TabStops;
+use crate::uax14::{self, BreakOpportunity};
+use crate::word::{is_cjk, Word, WordInfo, WordType};
 use peekmore::PeekMore;
+use std::collections::VecDeque;
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 /// Flags for Line
 ///
 /// - FLAG_BREAK_NONE: No break
 /// - FLAG_BREAK_ALL: Break all
+/// - FLAG_KEEP_ALL: Keep runs of CJK characters together like Latin words
 type Flags = u16;
-const FLAG_BREAK_NONE: u16 = 0000_0000_0000_0000;
-const FLAG_BREAK_ALL: u16 = 0000_0000_0000_0001;
+const FLAG_BREAK_NONE: u16 = 0b0000_0000_0000_0000;
+const FLAG_BREAK_ALL: u16 = 0b0000_0000_0000_0001;
+const FLAG_KEEP_ALL: u16 = 0b0000_0000_0000_0010;
+
+/// The characters that force a line break (class BK, CR, LF and NL of UAX #14).
+pub fn is_line_terminator(ch: char) -> bool {
+    matches!(
+        ch,
+        '\n' | '\r' | '\u{b}' | '\u{c}' | '\u{85}' | '\u{2028}' | '\u{2029}'
+    )
+}
+
+/// What ended a line that was broken by the text itself.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineTerminator {
+    /// `\n`
+    Lf,
+    /// `\r`
+    Cr,
+    /// `\r\n`, one break
+    CrLf,
+    /// U+0085 next line
+    Nel,
+    /// U+2028
+    LineSeparator,
+    /// U+2029
+    ParagraphSeparator,
+    /// U+000B
+    VerticalTab,
+    /// U+000C
+    FormFeed,
+}
+
+impl LineTerminator {
+    /// The terminator at the end of `text`, if any.
+    pub fn at_end(text: &str) -> Option<LineTerminator> {
+        if text.ends_with("\r\n") {
+            return Some(LineTerminator::CrLf);
+        }
+        Some(match text.chars().next_back()? {
+            '\n' => LineTerminator::Lf,
+            '\r' => LineTerminator::Cr,
+            '\u{85}' => LineTerminator::Nel,
+            '\u{2028}' => LineTerminator::LineSeparator,
+            '\u{2029}' => LineTerminator::ParagraphSeparator,
+            '\u{b}' => LineTerminator::VerticalTab,
+            '\u{c}' => LineTerminator::FormFeed,
+            _ => return None,
+        })
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineTerminator::Lf => "\n",
+            LineTerminator::Cr => "\r",
+            LineTerminator::CrLf => "\r\n",
+            LineTerminator::Nel => "\u{85}",
+            LineTerminator::LineSeparator => "\u{2028}",
+            LineTerminator::ParagraphSeparator => "\u{2029}",
+            LineTerminator::VerticalTab => "\u{b}",
+            LineTerminator::FormFeed => "\u{c}",
+        }
+    }
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct LinePosition {
@@ -23,6 +98,23 @@ pub struct LineInfo {
     pub line_spacing: usize,
     pub real_width: usize,
     pub ideal_width: usize,
+    /// The line breaks inside a word: a hyphen is drawn after `end`, `real_width` includes it.
+    pub hyphenated: bool,
+    /// Width of the punctuation that hangs before the start of the line, outside
+    /// `max_width`, see [`HangingPunctuation`]. `real_width` includes it.
+    pub hang_start: usize,
+    /// Width of the punctuation that hangs after `end`, outside `max_width`. `real_width`
+    /// includes it.
+    pub hang_end: usize,
+    /// URLs, e-mail addresses and paths shown on the line, whole: a link that is broken
+    /// across lines is reported on each of them.
+    pub links: Vec<Link>,
+    /// Where an autospace gap is drawn before the grapheme at that offset, between an
+    /// ideograph and a letter or digit. `real_width` includes the gaps.
+    pub autospace: Vec<usize>,
+    /// The line break of the text that ended the line, between `end` and `brk`. `None` when
+    /// the line was wrapped or ends the text.
+    pub terminator: Option<LineTerminator>,
 }
 
 impl LineInfo {
@@ -31,12 +123,142 @@ impl LineInfo {
     }
 }
 
-pub struct Line<'a> {
+/// How `Line` finds the places where a line may break.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum BreakEngine {
+    /// Word classes of the tokenizer plus kinsoku rules for punctuation and quotations.
+    #[default]
+    Heuristic,
+    /// The Unicode Line Breaking Algorithm (UAX #14) with its default rules, tailored by
+    /// [`LineBreakStrictness`].
+    Uax14,
+}
+
+/// Where lines may break inside words, as in CSS `word-break`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum WordBreak {
+    /// Latin words stay together, CJK text may break between any two characters.
+    #[default]
+    Normal,
+    /// Any word may break between any two characters.
+    BreakAll,
+    /// Runs of CJK characters stay together like Latin words, for Korean and some Chinese
+    /// UI strings; they only break at spaces and punctuation.
+    KeepAll,
+}
+
+/// How strictly line breaks around CJK punctuation and small kana are restricted, as in
+/// CSS `line-break`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum LineBreakStrictness {
+    /// The fewest restrictions, for short lines such as in newspapers: breaks are also
+    /// allowed before iteration marks, CJK hyphens and centered punctuation.
+    Loose,
+    /// The most common rules: breaks before small kana and `ー` are allowed.
+    #[default]
+    Normal,
+    /// The most restrictive rules: no line starts with small kana or `ー` either.
+    Strict,
+    /// A line may break between any two grapheme clusters, punctuation included.
+    Anywhere,
+}
+
+/// How the breaks of a paragraph are chosen among its break opportunities.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum WrapMode {
+    /// First fit: every line takes as much as fits, decided one line at a time. With the
+    /// heuristic engine, the words of a line are fitted as they come, unless hyphenation,
+    /// hanging or trimmed punctuation, autospace or white space change its paragraph.
+    #[default]
+    Greedy,
+    /// Total fit (Knuth–Plass): the breaks of a whole paragraph are chosen together to
+    /// minimise the demerits of its lines.
+    Optimal,
+    /// Lines of about the same length (`text-wrap: balance`): first fit at the narrowest
+    /// width that keeps the line count of [`WrapMode::Greedy`], for headings and captions.
+    Balance,
+    /// First fit, but the last lines of a paragraph are rewrapped when its last line would
+    /// be too short (`text-wrap: pretty`), see [`OrphanControl`].
+    Pretty,
+}
+
+/// How white space is handled, as in CSS `white-space`.
+///
+/// Collapsed white space keeps its bytes in the text, so positions still point into it: a
+/// run of spaces, tabs and, unless they are kept, line breaks takes the width of one space
+/// and none at the start of a paragraph. A renderer draws the first character of such a run
+/// as a space and skips the rest.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum WhiteSpace {
+    /// White space collapses and line breaks are spaces, lines wrap.
+    Normal,
+    /// White space collapses and line breaks are spaces, lines never wrap.
+    NoWrap,
+    /// White space and line breaks are kept, lines never wrap.
+    Pre,
+    /// White space and line breaks are kept, lines wrap and trailing spaces hang.
+    #[default]
+    PreWrap,
+    /// Spaces and tabs collapse, line breaks are kept and lines wrap.
+    PreLine,
+    /// Like [`WhiteSpace::PreWrap`], but spaces take their width at a line end too and a
+    /// line may break after every one of them.
+    BreakSpaces,
+}
+
+impl WhiteSpace {
+    pub fn collapses(self) -> bool {
+        matches!(
+            self,
+            WhiteSpace::Normal | WhiteSpace::NoWrap | WhiteSpace::PreLine
+        )
+    }
+
+    pub fn keeps_line_breaks(self) -> bool {
+        !matches!(self, WhiteSpace::Normal | WhiteSpace::NoWrap)
+    }
+
+    pub fn wraps(self) -> bool {
+        !matches!(self, WhiteSpace::NoWrap | WhiteSpace::Pre)
+    }
+
+    /// Whether `grapheme` collapses with the white space around it: a space, a tab or a line
+    /// break that is not kept.
+    pub fn is_collapsible(self, grapheme: &str) -> bool {
+        self.collapses()
+            && match grapheme {
+                " " | "\t" => true,
+                _ => !self.keeps_line_breaks() && LineTerminator::at_end(grapheme).is_some(),
+            }
+    }
+}
+
+pub struct Line<'a, M: WidthMeasurer = MonospaceMeasurer> {
     text: &'a str,
+    measurer: M,
+    engine: BreakEngine,
+    strictness: LineBreakStrictness,
+    uax14_breaks: Option<Vec<(usize, BreakOpportunity)>>,
+    wrap_mode: WrapMode,
+    orphan_control: OrphanControl,
+    hanging_punctuation: HangingPunctuation,
+    spacing_trim: SpacingTrim,
+    autospace: usize,
+    white_space: WhiteSpace,
+    hyphenator: Option<&'a Hyphenator>,
+    dictionary: Option<&'a Dictionary>,
+    links: TextLinks,
+    detect_links: bool,
+    keep_link_hosts: bool,
+    has_soft_hyphens: bool,
+    pending_lines: VecDeque<LineInfo>,
+    /// end of the paragraph whose segments the options leave as they are
+    words_end: usize,
 
     line_info_prev: Option<LineInfo>,
     max_width: usize,
     tab_width: usize,
+    tab_stops: TabStops,
     long_break: bool,
     letter_space: isize,
     flags: Flags,
@@ -44,17 +266,70 @@ pub struct Line<'a> {
 
 #[allow(dead_code)]
 impl Line<'_> {
-    pub fn new(text: &str, max_width: usize, tab_width: usize, letter_space: isize) -> Line {
+    pub fn new(text: &str, max_width: usize, tab_width: usize, letter_space: isize) -> Line<'_> {
         Line {
             text,
+            measurer: MonospaceMeasurer::default(),
+            engine: BreakEngine::Heuristic,
+            strictness: LineBreakStrictness::Normal,
+            uax14_breaks: None,
+            wrap_mode: WrapMode::Greedy,
+            orphan_control: OrphanControl::default(),
+            hanging_punctuation: HangingPunctuation::default(),
+            spacing_trim: SpacingTrim::default(),
+            autospace: 0,
+            white_space: WhiteSpace::default(),
+            hyphenator: None,
+            dictionary: None,
+            links: TextLinks::default(),
+            detect_links: false,
+            keep_link_hosts: false,
+            has_soft_hyphens: text.contains(paragraph::SOFT_HYPHEN),
+            pending_lines: VecDeque::new(),
+            words_end: 0,
             line_info_prev: None,
             max_width,
             tab_width,
+            tab_stops: TabStops::default(),
             long_break: false,
             letter_space,
             flags: FLAG_BREAK_NONE,
         }
     }
+}
+
+#[allow(dead_code)]
+impl<'a, M: WidthMeasurer> Line<'a, M> {
+    pub fn with_measurer<N: WidthMeasurer>(self, measurer: N) -> Line<'a, N> {
+        Line {
+            text: self.text,
+            measurer,
+            engine: self.engine,
+            strictness: self.strictness,
+            uax14_breaks: self.uax14_breaks,
+            wrap_mode: self.wrap_mode,
+            orphan_control: self.orphan_control,
+            hanging_punctuation: self.hanging_punctuation,
+            spacing_trim: self.spacing_trim,
+            autospace: self.autospace,
+            white_space: self.white_space,
+            hyphenator: self.hyphenator,
+            dictionary: self.dictionary,
+            links: self.links,
+            detect_links: self.detect_links,
+            keep_link_hosts: self.keep_link_hosts,
+            has_soft_hyphens: self.has_soft_hyphens,
+            pending_lines: self.pending_lines,
+            words_end: self.words_end,
+            line_info_prev: self.line_info_prev,
+            max_width: self.max_width,
+            tab_width: self.tab_width,
+            tab_stops: self.tab_stops,
+            long_break: self.long_break,
+            letter_space: self.letter_space,
+            flags: self.flags,
+        }
+    }
 
     pub fn with_max_width(mut self, max_width: usize) -> Self {
         self.max_width = max_width;
@@ -66,6 +341,11 @@ impl Line<'_> {
         self
     }
 
+    pub fn with_tab_stops(mut self, tab_stops: TabStops) -> Self {
+        self.tab_stops = tab_stops;
+        self
+    }
+
     pub fn with_long_break(mut self, long_break: bool) -> Self {
         self.long_break = long_break;
         self
@@ -75,12 +355,552 @@ impl Line<'_> {
         self.flags = flags;
         self
     }
+
+    pub fn with_word_break(mut self, word_break: WordBreak) -> Self {
+        self.flags &= !(FLAG_BREAK_ALL | FLAG_KEEP_ALL);
+        self.flags |= match word_break {
+            WordBreak::Normal => FLAG_BREAK_NONE,
+            WordBreak::BreakAll => FLAG_BREAK_ALL,
+            WordBreak::KeepAll => FLAG_KEEP_ALL,
+        };
+        self
+    }
+
+    pub fn with_break_engine(mut self, engine: BreakEngine) -> Self {
+        self.engine = engine;
+        self
+    }
+
+    pub fn with_line_break_strictness(mut self, strictness: LineBreakStrictness) -> Self {
+        self.strictness = strictness;
+        self
+    }
+
+    pub fn with_wrap_mode(mut self, wrap_mode: WrapMode) -> Self {
+        self.wrap_mode = wrap_mode;
+        self
+    }
+
+    pub fn with_orphan_control(mut self, orphan_control: OrphanControl) -> Self {
+        self.orphan_control = orphan_control;
+        self
+    }
+
+    pub fn with_hanging_punctuation(mut self, hanging_punctuation: HangingPunctuation) -> Self {
+        self.hanging_punctuation = hanging_punctuation;
+        self
+    }
+
+    pub fn with_spacing_trim(mut self, spacing_trim: SpacingTrim) -> Self {
+        self.spacing_trim = spacing_trim;
+        self
+    }
+
+    pub fn with_autospace(mut self, gap: usize) -> Self {
+        self.autospace = gap;
+        self
+    }
+
+    pub fn with_white_space(mut self, white_space: WhiteSpace) -> Self {
+        self.white_space = white_space;
+        self
+    }
+
+    pub fn with_hyphenator(mut self, hyphenator: &'a Hyphenator) -> Self {
+        self.hyphenator = Some(hyphenator);
+        self
+    }
+
+    pub fn with_dictionary(mut self, dictionary: &'a Dictionary) -> Self {
+        self.dictionary = Some(dictionary);
+        self
+    }
+
+    pub fn with_link_detection(mut self, detect_links: bool) -> Self {
+        self.detect_links = detect_links;
+        self.update_links();
+        self
+    }
+
+    pub fn with_keep_link_hosts(mut self, keep_hosts: bool) -> Self {
+        self.keep_link_hosts = keep_hosts;
+        self.update_links();
+        self
+    }
+
+    fn update_links(&mut self) {
+        self.links = if self.detect_links {
+            TextLinks::new(self.text, self.keep_link_hosts)
+        } else {
+            TextLinks::default()
+        };
+    }
 }
 
-impl Iterator for Line<'_> {
+impl<M: WidthMeasurer> Iterator for Line<'_, M> {
     type Item = LineInfo;
 
     fn next(&mut self) -> Option<Self::Item> {
+        let mut line_info = match (self.wrap_mode, self.engine) {
+            (WrapMode::Greedy, BreakEngine::Heuristic) if self.fits_words() => {
+                match self.next_heuristic() {
+                    Some(line_info) if self.is_valid_line(&line_info) => Some(line_info),
+                    // the segments take over the paragraph of a line that would not advance
+                    // or would break inside a character
+                    Some(_) => self.next_paragraph(),
+                    None => None,
+                }
+            }
+            // hyphenation points, hanging and trimmed punctuation, autospace gaps and white
+            // space are handled on the segments of a whole paragraph
+            _ => self.next_paragraph(),
+        }?;
+        // the line terminator is never part of the content
+        let position = &mut line_info.position;
+        let content = &self.text[position.start..position.end.max(position.start)];
+        position.end = position.start + content.trim_end_matches(is_line_terminator).len();
+        if self.white_space.keeps_line_breaks() {
+            line_info.terminator =
+                LineTerminator::at_end(&self.text[position.end.min(position.brk)..position.brk]);
+        }
+
+        let position = &line_info.position;
+        line_info.links = self
+            .links
+            .overlapping(position.start, position.end.min(position.brk));
+        if self.autospace > 0 {
+            let (start, end) = (position.start, position.end.min(position.brk));
+            line_info.autospace = paragraph::autospace_positions(&self.text[start..end])
+                .into_iter()
+                .map(|v| start + v)
+                .collect();
+        }
+        self.line_info_prev = Some(line_info.clone());
+        Some(line_info)
+    }
+}
+
+impl<M: WidthMeasurer> Line<'_, M> {
+    fn measure(&self, text: &str) -> usize {
+        self.measure_at(text, 0)
+    }
+
+    /// Width of `text` starting at `column`, where its tabs advance from.
+    fn measure_at(&self, text: &str, column: usize) -> usize {
+        self.measure_prefix(text, text.len(), column)
+    }
+
+    /// Width of `self.text[range]` starting at `column`, its tabs line up the text after them
+    /// up to `context_end`.
+    fn measure_range(&self, range: Range<usize>, column: usize, context_end: usize) -> usize {
+        self.measure_prefix(&self.text[range.start..context_end], range.len(), column)
+    }
+
+    /// Width of `text[..len]` starting at `column`, its tabs line up the text after them up
+    /// to the end of `text`.
+    fn measure_prefix(&self, text: &str, len: usize, column: usize) -> usize {
+        let mut width = 0;
+        let mut collapsing = false;
+        for (offset, grapheme) in text[..len].grapheme_indices(true) {
+            let after = &text[offset + grapheme.len()..];
+            // a run of collapsible white space is one space
+            if self.white_space.is_collapsible(grapheme) {
+                if !collapsing {
+                    width += self
+                        .grapheme_width(" ", after, column + width)
+                        .saturating_add_signed(self.letter_space);
+                }
+                collapsing = true;
+                continue;
+            }
+            collapsing = false;
+            width += self
+                .grapheme_width(grapheme, after, column + width)
+                .saturating_add_signed(self.letter_space);
+        }
+        width
+    }
+
+    /// Width of `grapheme` at `column`, a tab advances to the next tab stop and lines up the
+    /// text `after` it.
+    fn grapheme_width(&self, grapheme: &str, after: &str, column: usize) -> usize {
+        if grapheme != "\t" {
+            return self.measurer.grapheme_width(grapheme, self.tab_width);
+        }
+        self.tab_stops
+            .advance(column, self.tab_width, after, |v| self.measure_at(v, 0))
+    }
+
+    fn is_keep_all(&self) -> bool {
+        (self.flags & FLAG_KEEP_ALL) == FLAG_KEEP_ALL
+    }
+
+    /// Words of the text from `start` on, tokenized with the settings of this line.
+    fn words(&self, start: usize, remaining_width: usize) -> Word<'_, M> {
+        Word::new(
+            &self.text[start..],
+            remaining_width,
+            self.tab_width,
+            self.letter_space,
+        )
+        .with_measurer(&self.measurer)
+        .with_strictness(self.strictness)
+        .with_keep_all(self.is_keep_all())
+        .with_dictionary(self.dictionary)
+        .with_links(&self.links, start)
+        .with_tab_stops(&self.tab_stops)
+        .with_white_space(self.white_space)
+    }
+
+    /// Words of the paragraph at `start` for the segments of the heuristic engine, which
+    /// break all words themselves.
+    fn heuristic_words(&self, start: usize) -> Word<'_, M> {
+        self.words(start, usize::MAX)
+            .with_break_all(self.flags & FLAG_BREAK_ALL == FLAG_BREAK_ALL)
+    }
+
+    /// Width the lines are wrapped at, none when white space does not wrap.
+    fn wrap_width(&self) -> usize {
+        if self.white_space.wraps() {
+            self.max_width
+        } else {
+            usize::MAX
+        }
+    }
+
+    /// Segments of the paragraph starting at `start`, as found by the break engine.
+    fn paragraph_segments(&mut self, start: usize) -> Vec<Segment> {
+        let segments = match self.engine {
+            BreakEngine::Heuristic => paragraph::word_segments(start, self.heuristic_words(start)),
+            BreakEngine::Uax14 => {
+                if self.uax14_breaks.is_none() {
+                    let mut breaks = self.links.replace_breaks(uax14::line_breaks(
+                        self.text,
+                        self.strictness,
+                        self.is_keep_all(),
+                        self.dictionary,
+                    ));
+                    // line breaks that are spaces only allow a break
+                    if !self.white_space.keeps_line_breaks() {
+                        for (pos, opportunity) in breaks.iter_mut() {
+                            if self.text[..*pos].ends_with(is_line_terminator) {
+                                *opportunity = BreakOpportunity::Allowed;
+                            }
+                        }
+                    }
+                    self.uax14_breaks = Some(breaks);
+                }
+                let breaks = self.uax14_breaks.as_deref().unwrap_or_default();
+                paragraph::uax14_segments(self.text, start, breaks, |range, column| {
+                    self.measure_range(range, column, self.text.len())
+                })
+            }
+        };
+
+        let segments = match self.hyphenator {
+            Some(hyphenator) => paragraph::hyphenate_segments(
+                self.text,
+                segments,
+                hyphenator,
+                &self.links,
+                self.measure("-"),
+                |v| self.measure(v),
+            ),
+            None => segments,
+        };
+        let segments = if self.has_soft_hyphens {
+            paragraph::soft_hyphen_segments(self.text, segments, self.measure("-"), |v| {
+                self.measure(v)
+            })
+        } else {
+            segments
+        };
+
+        let segments = if self.long_break {
+            paragraph::split_long_segments(
+                self.text,
+                segments,
+                self.wrap_width(),
+                |range, column| self.measure_range(range, column, self.text.len()),
+                |prev, next| self.can_cut_between(prev, next),
+            )
+        } else {
+            segments
+        };
+
+        let segments = if self.white_space.collapses() {
+            paragraph::collapse_segments(
+                self.text,
+                segments,
+                |v| self.white_space.is_collapsible(v),
+                |v| self.measure(v),
+            )
+        } else {
+            segments
+        };
+        let segments = if self.white_space == WhiteSpace::BreakSpaces {
+            paragraph::break_spaces_segments(self.text, segments, |v| self.measure(v))
+        } else {
+            segments
+        };
+
+        let segments =
+            paragraph::trim_segments(self.text, segments, self.spacing_trim, |v| self.measure(v));
+        let segments = paragraph::autospace_segments(self.text, segments, self.autospace);
+
+        if self.hanging_punctuation != HangingPunctuation::default() {
+            paragraph::hang_segments(self.text, segments, &self.hanging_punctuation, |v| {
+                self.measure(v)
+            })
+        } else {
+            segments
+        }
+    }
+
+    fn line_from_segments(segments: &[Segment], max_width: usize) -> LineInfo {
+        let first = &segments[0];
+        let last = &segments[segments.len() - 1];
+        let width = paragraph::line_width(segments);
+        let hyphen = last.hyphen.unwrap_or(0);
+        // punctuation at the end only hangs when it would not fit otherwise
+        let hang_end = if width - first.hang_start > max_width {
+            last.hang_end
+        } else {
+            0
+        };
+
+        LineInfo {
+            position: LinePosition {
+                start: first.start,
+                end: last.end,
+                brk: last.brk,
+            },
+            line_height: 0,
+            line_spacing: 0,
+            real_width: width,
+            ideal_width: width - hyphen,
+            hyphenated: last.hyphen.is_some(),
+            hang_start: first.hang_start,
+            hang_end,
+            links: vec![],
+            autospace: vec![],
+            terminator: None,
+        }
+    }
+
+    /// A greedy heuristic line is fitted over the words of the text, unless an option changes
+    /// the segments of its paragraph.
+    fn fits_words(&mut self) -> bool {
+        if !self.pending_lines.is_empty() || self.white_space != WhiteSpace::PreWrap {
+            return false;
+        }
+        if self.hyphenator.is_none()
+            && !self.has_soft_hyphens
+            && self.hanging_punctuation == HangingPunctuation::default()
+            && self.spacing_trim == SpacingTrim::SpaceAll
+            && self.autospace == 0
+        {
+            return true;
+        }
+
+        let start = self.line_info_prev.as_ref().map_or(0, |v| v.position.brk);
+        if start < self.words_end || start >= self.text.len() {
+            return true;
+        }
+        let segments = paragraph::word_segments(start, self.heuristic_words(start));
+        let segments = if self.long_break {
+            paragraph::split_long_segments(
+                self.text,
+                segments,
+                self.max_width,
+                |range, column| self.measure_range(range, column, self.text.len()),
+                |prev, next| self.can_cut_between(prev, next),
+            )
+        } else {
+            segments
+        };
+        if segments != self.paragraph_segments(start) {
+            return false;
+        }
+        self.words_end = segments.last().map_or(start, |v| v.brk);
+        true
+    }
+
+    fn is_valid_line(&self, line_info: &LineInfo) -> bool {
+        let LinePosition { start, end, brk } = line_info.position;
+        brk > start
+            && brk <= self.text.len()
+            && self.text.is_char_boundary(brk)
+            && self.text.is_char_boundary(end.min(self.text.len()))
+    }
+
+    /// Lines of a whole paragraph are computed at once and handed out one by one.
+    fn next_paragraph(&mut self) -> Option<LineInfo> {
+        if self.pending_lines.is_empty() {
+            let mut start = self.line_info_prev.as_ref().map_or(0, |v| v.position.brk);
+            start += self.collapsible_len(start);
+            if start >= self.text.len() {
+                return None;
+            }
+
+            let segments = self.paragraph_segments(start);
+            let max_width = self.wrap_width();
+            // tabs advance from the start of their line, which the segments do not know yet
+            let has_tabs = segments
+                .iter()
+                .any(|v| self.text[v.start..v.end].contains('\t'));
+            let columns = paragraph::segment_columns(&segments);
+            let is_greedy = self.wrap_mode == WrapMode::Greedy || !self.white_space.wraps();
+            let breaks = match self.wrap_mode {
+                _ if is_greedy && has_tabs => self.greedy_tab_breaks(&segments, &columns),
+                _ if !self.white_space.wraps() => paragraph::greedy_breaks(&segments, max_width),
+                WrapMode::Greedy => paragraph::greedy_breaks(&segments, max_width),
+                WrapMode::Optimal => paragraph::optimal_breaks(&segments, max_width),
+                WrapMode::Balance => paragraph::balanced_breaks(&segments, max_width),
+                WrapMode::Pretty => {
+                    paragraph::pretty_breaks(self.text, &segments, max_width, &self.orphan_control)
+                }
+            };
+            let mut line_start = 0;
+            for line_end in breaks {
+                let line = &segments[line_start..line_end];
+                let mut line_info = if has_tabs {
+                    let context_end = line[line.len() - 1].end;
+                    let line = self.retab(line, columns[line_start], context_end);
+                    Self::line_from_segments(&line, max_width)
+                } else {
+                    Self::line_from_segments(line, max_width)
+                };
+                // a line that never wraps has no line end for its trailing spaces to hang at,
+                // they stay part of it; the line terminator is taken off in `next`
+                if !self.white_space.wraps() {
+                    let space_width = line[line.len() - 1].space_width;
+                    line_info.position.end = line_info.position.brk;
+                    line_info.real_width += space_width;
+                    line_info.ideal_width += space_width;
+                }
+                self.pending_lines.push_back(line_info);
+                line_start = line_end;
+            }
+        }
+
+        self.pending_lines.pop_front()
+    }
+
+    /// First fit over `segments` with tabs, which are measured again from the start of every
+    /// line. `columns` are where the segments start in the paragraph.
+    fn greedy_tab_breaks(&self, segments: &[Segment], columns: &[usize]) -> Vec<usize> {
+        let mut breaks = vec![];
+        let mut start = 0;
+        while start < segments.len() {
+            let line = self.retab(&segments[start..], columns[start], self.text.len());
+            start += paragraph::greedy_breaks(&line, self.wrap_width())[0];
+            breaks.push(start);
+        }
+        breaks
+    }
+
+    /// `segments` that start a line, with their tabs advanced from the start of the line
+    /// instead of from `column`, where the first one starts in the paragraph. The text a tab
+    /// lines up ends at `context_end` at the latest.
+    fn retab(&self, segments: &[Segment], mut column: usize, context_end: usize) -> Vec<Segment> {
+        let mut line_column = 0;
+        let mut result = Vec::with_capacity(segments.len());
+        for segment in segments {
+            let mut retabbed = segment.clone();
+            let range = segment.start..segment.end;
+            if self.text[range.clone()].contains('\t') {
+                let in_paragraph = self.measure_range(range.clone(), column, self.text.len());
+                let in_line = self.measure_range(range, line_column, context_end);
+                retabbed.width = (segment.width + in_line).saturating_sub(in_paragraph);
+            }
+            column += segment.width + segment.space_width;
+            line_column += retabbed.width + retabbed.space_width;
+            result.push(retabbed);
+        }
+        result
+    }
+
+    /// Break opportunities of the paragraph at `start`, none past the end of the text.
+    pub(crate) fn paragraph_opportunities(&mut self, start: usize) -> Vec<BreakInfo> {
+        let start = start + self.collapsible_len(start);
+        if start >= self.text.len() {
+            return vec![];
+        }
+
+        let segments = self.paragraph_segments(start);
+        let mut opportunities = vec![];
+        for segment in &segments {
+            let content = &self.text[segment.start..segment.end];
+            // width of the positions already reported inside the segment
+            let mut taken = 0;
+            let mut prev = None;
+            for (offset, grapheme) in content.grapheme_indices(true) {
+                if prev.is_some_and(|prev| self.is_kinsoku_between(prev, grapheme)) {
+                    let width = self.measure(&content[..offset]).saturating_sub(taken);
+                    taken += width;
+                    opportunities.push(BreakInfo {
+                        position: segment.start + offset,
+                        kind: BreakKind::ProhibitedByKinsoku,
+                        width,
+                        space_width: 0,
+                        hyphen_width: 0,
+                    });
+                }
+                prev = Some(grapheme);
+            }
+
+            let kind = if segment.hyphen.is_some() {
+                BreakKind::AllowedWithHyphen
+            } else if segment.brk >= self.text.len()
+                || (self.white_space.keeps_line_breaks()
+                    && LineTerminator::at_end(&self.text[..segment.brk]).is_some())
+            {
+                BreakKind::Mandatory
+            } else {
+                BreakKind::Allowed
+            };
+            opportunities.push(BreakInfo {
+                position: segment.brk,
+                kind,
+                width: segment.width.saturating_sub(taken),
+                space_width: segment.space_width,
+                hyphen_width: segment.hyphen.unwrap_or(0),
+            });
+        }
+
+        opportunities
+    }
+
+    /// A segment that is too long may be cut between the graphemes `prev` and `next` by the
+    /// rules of the break engine.
+    fn can_cut_between(&self, prev: &str, next: &str) -> bool {
+        match self.engine {
+            BreakEngine::Heuristic => paragraph::can_cut_between(prev, next, self.strictness),
+            BreakEngine::Uax14 => uax14::can_cut_between(prev, next, self.strictness),
+        }
+    }
+
+    /// A line would break between `prev` and `next` if not for the kinsoku rules: one of
+    /// them is CJK and `next` may not start a line or `prev` may not end one.
+    fn is_kinsoku_between(&self, prev: &str, next: &str) -> bool {
+        let is_cjk = |v: &str| v.chars().any(is_cjk) || v.width() == 2;
+        let prohibited = WordType::classify(next, self.strictness) == WordType::CLOSE_PUNCTUATION
+            || WordType::classify(prev, self.strictness) == WordType::OPEN_PUNCTUATION;
+        prohibited && (is_cjk(prev) || is_cjk(next))
+    }
+
+    /// Length of the collapsible white space at `start`, which is not shown at the start of
+    /// a paragraph.
+    fn collapsible_len(&self, start: usize) -> usize {
+        self.text[start..]
+            .graphemes(true)
+            .take_while(|v| self.white_space.is_collapsible(v))
+            .map(str::len)
+            .sum()
+    }
+
+    fn next_heuristic(&mut self) -> Option<LineInfo> {
         let mut line_info = LineInfo {
             position: LinePosition {
                 start: self.line_info_prev.as_ref().map_or(0, |v| v.position.brk),
@@ -91,15 +911,17 @@ impl Iterator for Line<'_> {
             line_spacing: 0,
             real_width: 0,
             ideal_width: 0,
+            hyphenated: false,
+            hang_start: 0,
+            hang_end: 0,
+            links: vec![],
+            autospace: vec![],
+            terminator: None,
         };
 
-        let mut word_iter = Word::new(
-            &self.text[line_info.position.start..],
-            self.max_width,
-            self.tab_width,
-            self.letter_space,
-        )
-        .peekmore();
+        let mut word_iter = self
+            .words(line_info.position.start, self.max_width)
+            .peekmore();
 
         let break_all = (self.flags & FLAG_BREAK_ALL) == FLAG_BREAK_ALL;
 
@@ -143,7 +965,7 @@ impl Iterator for Line<'_> {
             }
 
             if is_line_leading
-                && self.long_break == true
+                && self.long_break
                 && word.position.brk != usize::MAX
                 && !(word.word_type == WordType::RETURN || word.word_type == WordType::NEWLINE)
             {
@@ -182,8 +1004,9 @@ impl Iterator for Line<'_> {
                             continue;
                         }
 
-                        if unresolved_op_qu.is_some() && unresolved_op_qu_word_count == 0 {
-                            let qu = unresolved_op_qu.unwrap();
+                        if let Some(qu) =
+                            unresolved_op_qu.filter(|_| unresolved_op_qu_word_count == 0)
+                        {
                             end = qu.position.start;
                             brk = qu.position.start;
                         } else {
@@ -216,8 +1039,10 @@ impl Iterator for Line<'_> {
 
                     if word_next.position.brk == word_next.position.end {
                         if word_next.word_type == WordType::CJK
+                            || word_next.word_type == WordType::EMOJI
                             || word_next.word_type == WordType::LATIN
                             || word_next.word_type == WordType::NUMBER
+                            || word_next.word_type == WordType::LINK
                         {
                             continue;
                         } else if word_next.word_type == WordType::SPACE
@@ -238,6 +1063,7 @@ impl Iterator for Line<'_> {
                                 brk = word_next.position.brk;
                                 real_width += word_next.real_width;
                                 ideal_width += word_next.ideal_width;
+                                should_take_new_one = true;
                             }
                             break;
                         }
@@ -246,7 +1072,7 @@ impl Iterator for Line<'_> {
                     if word_next.word_type == WordType::RETURN
                         || word_next.word_type == WordType::NEWLINE
                     {
-                        brk += 1;
+                        brk = word_next.position.end;
                     } else if !(word.word_type == WordType::CLOSE_PUNCTUATION
                         || word.word_type == WordType::QUOTATION)
                         && (word_next.word_type == WordType::CLOSE_PUNCTUATION
@@ -257,8 +1083,9 @@ impl Iterator for Line<'_> {
                             end = word_next.position.end;
                             brk = word_next.position.brk;
                         } else {
-                            if unresolved_op_qu.is_some() && unresolved_op_qu_word_count == 0 {
-                                let op_qu = unresolved_op_qu.unwrap();
+                            if let Some(op_qu) =
+                                unresolved_op_qu.filter(|_| unresolved_op_qu_word_count == 0)
+                            {
                                 end = op_qu.position.start;
                                 brk = op_qu.position.start;
                             } else {
@@ -267,7 +1094,7 @@ impl Iterator for Line<'_> {
                             }
                         }
 
-                        if is_line_leading == false {
+                        if !is_line_leading {
                             real_width -= word.real_width;
                             ideal_width -= word.ideal_width;
                         } else {
@@ -276,13 +1103,14 @@ impl Iterator for Line<'_> {
                         }
                     }
                     break;
-                } else if word.word_type == WordType::CJK
+                } else if (word.word_type == WordType::CJK
+                    || word.word_type == WordType::EMOJI
                     || word.word_type == WordType::LATIN
                     || word.word_type == WordType::NUMBER
+                    || word.word_type == WordType::LINK)
+                    && unresolved_op_qu.is_some()
                 {
-                    if unresolved_op_qu.is_some() {
-                        unresolved_op_qu_word_count += 1;
-                    }
+                    unresolved_op_qu_word_count += 1;
                 }
             } else {
                 end = word.position.end;
@@ -308,11 +1136,23 @@ impl Iterator for Line<'_> {
             }
         }
 
+        // trailing spaces hang after the line end, they are skipped by `brk` but not displayed
+        if end <= brk {
+            let content = &self.text[line_info.position.start..line_info.position.start + end];
+            let trailing = content.len() - content.trim_end_matches(' ').len();
+            let space_width = self
+                .measurer
+                .char_width(' ', self.tab_width)
+                .saturating_add_signed(self.letter_space);
+            end -= trailing;
+            real_width = real_width.saturating_sub(trailing * space_width);
+            ideal_width = ideal_width.saturating_sub(trailing * space_width);
+        }
+
         line_info.position.end = line_info.position.start + end;
         line_info.position.brk = line_info.position.start + brk;
         line_info.real_width = real_width;
         line_info.ideal_width = ideal_width;
-        self.line_info_prev = Some(line_info.clone());
         Some(line_info)
     }
 }
@@ -446,4 +1286,18 @@ mod tests {
     fn test_line_11() {
         do_a_test!("f abcdefghijklmnopq", 10, FLAG_BREAK_ALL);
     }
+
+    #[test]
+    fn test_line_terminator() {
+        assert_eq!(LineTerminator::at_end("ab\r\n"), Some(LineTerminator::CrLf));
+        assert_eq!(LineTerminator::at_end("ab\r"), Some(LineTerminator::Cr));
+        assert_eq!(
+            LineTerminator::at_end("\u{2029}"),
+            Some(LineTerminator::ParagraphSeparator)
+        );
+        assert_eq!(LineTerminator::at_end("ab"), None);
+        assert_eq!(LineTerminator::at_end(""), None);
+        assert!(is_line_terminator('\u{85}'));
+        assert!(!is_line_terminator('\t'));
+    }
 }
@@ -0,0 +1,399 @@
+//! URLs, e-mail addresses and file paths, which break at their separators instead of at
+//! the punctuation rules of prose.
+
+use crate::uax14::BreakOpportunity;
+use unicode_width::UnicodeWidthChar;
+
+/// What a [`Link`] points to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LinkKind {
+    /// `https://example.com/a?b=c`, `www.example.com`, `mailto:a@example.com`
+    Url,
+    /// `someone@example.com`
+    Email,
+    /// `/usr/local/bin`, `~/notes.txt`, `./src`, `C:\Users`
+    Path,
+}
+
+/// A URL, e-mail address or file path found in the text, as byte offsets.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Link {
+    pub kind: LinkKind,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Link {
+    pub fn slices<'a>(&self, string: &'a str) -> &'a str {
+        &string[self.start..self.end]
+    }
+}
+
+/// The links of a text and the places where lines may break inside them.
+#[derive(Debug, Clone, Default)]
+pub struct TextLinks {
+    pub links: Vec<Link>,
+    /// sorted byte offsets, a line may break before each of them
+    pub breaks: Vec<usize>,
+}
+
+pub static NO_LINKS: TextLinks = TextLinks {
+    links: Vec::new(),
+    breaks: Vec::new(),
+};
+
+impl TextLinks {
+    /// Find the links of `text`, with `keep_hosts` the host of a URL and the domain of an
+    /// e-mail address do not break.
+    pub fn new(text: &str, keep_hosts: bool) -> TextLinks {
+        let mut links = TextLinks::default();
+        let mut pos = 0;
+        while let Some((offset, _)) = text[pos..].char_indices().find(|&(i, _)| {
+            // links start at the beginning of a word
+            !text[..pos + i].ends_with(|ch: char| ch.is_alphanumeric() || ch == '_')
+        }) {
+            let start = pos + offset;
+            match find_link(text, start) {
+                Some(link) => {
+                    links.breaks.extend(link_breaks(text, &link, keep_hosts));
+                    links.links.push(link);
+                    pos = link.end;
+                }
+                None => pos = start + text[start..].chars().next().map_or(1, char::len_utf8),
+            }
+        }
+        links
+    }
+
+    /// The link that `pos` is part of.
+    pub fn containing(&self, pos: usize) -> Option<&Link> {
+        let i = self.links.partition_point(|v| v.end <= pos);
+        self.links.get(i).filter(|v| v.start <= pos)
+    }
+
+    pub fn is_link_start(&self, pos: usize) -> bool {
+        self.containing(pos).is_some_and(|v| v.start == pos)
+    }
+
+    pub fn is_break(&self, pos: usize) -> bool {
+        self.breaks.binary_search(&pos).is_ok()
+    }
+
+    /// Break opportunities of another engine, with those inside links replaced by the
+    /// breaks of the links.
+    pub fn replace_breaks(
+        &self,
+        breaks: Vec<(usize, BreakOpportunity)>,
+    ) -> Vec<(usize, BreakOpportunity)> {
+        if self.links.is_empty() {
+            return breaks;
+        }
+
+        let mut breaks: Vec<(usize, BreakOpportunity)> = breaks
+            .into_iter()
+            .filter(|v| self.containing(v.0).is_none_or(|link| link.start == v.0))
+            .chain(self.breaks.iter().map(|&v| (v, BreakOpportunity::Allowed)))
+            .collect();
+        breaks.sort_by_key(|v| v.0);
+        breaks
+    }
+
+    /// Links that share text with `start..end`.
+    pub fn overlapping(&self, start: usize, end: usize) -> Vec<Link> {
+        let i = self.links.partition_point(|v| v.end <= start);
+        self.links[i..]
+            .iter()
+            .take_while(|v| v.start < end)
+            .copied()
+            .collect()
+    }
+}
+
+/// The link starting at `start`, if there is one.
+fn find_link(text: &str, start: usize) -> Option<Link> {
+    let rest = &text[start..];
+    let kind = if scheme_len(rest).is_some() || starts_with_ignore_case(rest, "www.") {
+        LinkKind::Url
+    } else if is_path_start(rest) {
+        LinkKind::Path
+    } else if is_email_start(rest) {
+        LinkKind::Email
+    } else {
+        return None;
+    };
+
+    let end = start + link_len(rest);
+    let link = Link { kind, start, end };
+    let valid = match kind {
+        LinkKind::Url => end > start + scheme_len(rest).unwrap_or(4),
+        LinkKind::Email => is_email(link.slices(text)),
+        LinkKind::Path => is_path(link.slices(text)),
+    };
+    valid.then_some(link)
+}
+
+fn starts_with_ignore_case(text: &str, prefix: &str) -> bool {
+    text.get(..prefix.len())
+        .is_some_and(|v| v.eq_ignore_ascii_case(prefix))
+}
+
+/// Length of `https://`, `mailto:` and other schemes with their separator.
+fn scheme_len(text: &str) -> Option<usize> {
+    if starts_with_ignore_case(text, "mailto:") {
+        return Some("mailto:".len());
+    }
+    let name_len = text
+        .find(|ch: char| !(ch.is_ascii_alphanumeric() || matches!(ch, '+' | '.' | '-')))
+        .unwrap_or(text.len());
+    let is_name = name_len > 1 && text.starts_with(|ch: char| ch.is_ascii_alphabetic());
+    (is_name && text[name_len..].starts_with("://")).then_some(name_len + "://".len())
+}
+
+fn is_path_start(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    ["/", "~/", "./", "../"].iter().any(|v| text.starts_with(v))
+        || (bytes.len() > 2
+            && bytes[0].is_ascii_alphabetic()
+            && bytes[1] == b':'
+            && matches!(bytes[2], b'\\' | b'/'))
+}
+
+/// A path has at least one named part after its first separator: `/usr/bin` or `~/notes`,
+/// but not `/` or `/usr`.
+fn is_path(path: &str) -> bool {
+    let is_absolute = path.starts_with('/');
+    let names = path
+        .split(['/', '\\'])
+        .filter(|v| !v.is_empty() && !matches!(*v, "~" | "." | ".."))
+        .count();
+    names >= if is_absolute { 2 } else { 1 } && !path.contains("//")
+}
+
+fn is_email_local(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || matches!(ch, '.' | '_' | '%' | '+' | '-')
+}
+
+fn is_email_start(text: &str) -> bool {
+    let local_len = text.find(|ch| !is_email_local(ch)).unwrap_or(text.len());
+    local_len > 0 && text[local_len..].starts_with('@')
+}
+
+/// `local@example.com`: a domain of at least two labels.
+fn is_email(email: &str) -> bool {
+    let Some((_, domain)) = email.split_once('@') else {
+        return false;
+    };
+    let labels: Vec<&str> = domain.split('.').collect();
+    labels.len() >= 2
+        && labels
+            .iter()
+            .all(|v| !v.is_empty() && v.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '-'))
+}
+
+/// Length of the link at the start of `text`: up to a space or a character that cannot be
+/// part of it, without the punctuation that ends the sentence around it.
+fn link_len(text: &str) -> usize {
+    let mut len = text
+        .find(|ch: char| {
+            ch.is_whitespace()
+                || matches!(ch, '<' | '>' | '"' | '`' | '\u{ad}')
+                || ch.width() == Some(2)
+        })
+        .unwrap_or(text.len());
+
+    loop {
+        let link = &text[..len];
+        let Some(last) = link.chars().next_back() else {
+            return 0;
+        };
+        let unbalanced = |open: char| link.matches(open).count() < link.matches(last).count();
+        let is_trailing = match last {
+            '.' | ',' | ';' | ':' | '!' | '?' | '\'' | '*' => true,
+            ')' => unbalanced('('),
+            ']' => unbalanced('['),
+            '}' => unbalanced('{'),
+            _ => false,
+        };
+        if !is_trailing {
+            return len;
+        }
+        len -= last.len_utf8();
+    }
+}
+
+/// Byte offsets inside `link` where a line may break.
+///
+/// A URL breaks after `/` and before `?`, `&` and `#` but never inside its scheme, an e-mail
+/// address breaks before `@`, and both break before the dots of their host unless
+/// `keep_hosts`. A path breaks after its separators.
+fn link_breaks(text: &str, link: &Link, keep_hosts: bool) -> Vec<usize> {
+    let slice = link.slices(text);
+    let mut breaks = vec![];
+    let mut push = |offset: usize| {
+        if offset > 0 && offset < slice.len() {
+            breaks.push(link.start + offset);
+        }
+    };
+
+    match link.kind {
+        LinkKind::Url => {
+            let host_start = scheme_len(slice).unwrap_or(0);
+            let host_end = slice[host_start..]
+                .find(['/', '?', '#'])
+                .map_or(slice.len(), |v| host_start + v);
+            for (i, ch) in slice.char_indices() {
+                if i < host_start {
+                    continue;
+                }
+                match ch {
+                    '.' if i < host_end && i > host_start && !keep_hosts => push(i),
+                    '/' if i >= host_end && !slice[i + 1..].starts_with('/') => push(i + 1),
+                    '?' | '&' | '#' if i >= host_end => push(i),
+                    _ => {}
+                }
+            }
+        }
+        LinkKind::Email => {
+            let at = slice.find('@').unwrap_or(0);
+            push(at);
+            for (i, ch) in slice[at..].char_indices() {
+                if ch == '.' && !keep_hosts {
+                    push(at + i);
+                }
+            }
+        }
+        LinkKind::Path => {
+            for (i, ch) in slice.char_indices() {
+                // not after a prefix such as `/`, `~/`, `../` or `C:\`
+                let named = slice[..i]
+                    .rsplit(['/', '\\'])
+                    .next()
+                    .is_some_and(|v| !v.is_empty() && !v.ends_with(['~', '.', ':']));
+                if matches!(ch, '/' | '\\') && named {
+                    push(i + 1);
+                }
+            }
+        }
+    }
+
+    breaks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pieces(text: &str, keep_hosts: bool) -> Vec<Vec<&str>> {
+        let links = TextLinks::new(text, keep_hosts);
+        links
+            .links
+            .iter()
+            .map(|link| {
+                let mut pieces = vec![];
+                let mut start = link.start;
+                for &pos in links
+                    .breaks
+                    .iter()
+                    .filter(|&&v| v > link.start && v < link.end)
+                {
+                    pieces.push(&text[start..pos]);
+                    start = pos;
+                }
+                pieces.push(&text[start..link.end]);
+                pieces
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_find_links() {
+        let text = "See https://example.com/a/b?x=1&y=2#top, mail me@example.org or \
+                    open ~/notes.txt (or /usr/local/bin). Not: and/or, 10/17, a@b, /usr.";
+        let links = TextLinks::new(text, false);
+        let found: Vec<(LinkKind, &str)> = links
+            .links
+            .iter()
+            .map(|v| (v.kind, v.slices(text)))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (LinkKind::Url, "https://example.com/a/b?x=1&y=2#top"),
+                (LinkKind::Email, "me@example.org"),
+                (LinkKind::Path, "~/notes.txt"),
+                (LinkKind::Path, "/usr/local/bin"),
+            ]
+        );
+
+        let text = "(www.example.com/wiki/Rust_(language)) C:\\Users\\me mailto:a@b.co";
+        let found: Vec<&str> = TextLinks::new(text, false)
+            .links
+            .iter()
+            .map(|v| v.slices(text))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                "www.example.com/wiki/Rust_(language)",
+                "C:\\Users\\me",
+                "mailto:a@b.co"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_link_breaks() {
+        assert_eq!(
+            pieces("https://docs.example.com/a/b?x=1&y=2#top", false),
+            [vec![
+                "https://docs",
+                ".example",
+                ".com/",
+                "a/",
+                "b",
+                "?x=1",
+                "&y=2",
+                "#top"
+            ]]
+        );
+        assert_eq!(
+            pieces("https://docs.example.com/a/b?x=1&y=2#top", true),
+            [vec![
+                "https://docs.example.com/",
+                "a/",
+                "b",
+                "?x=1",
+                "&y=2",
+                "#top"
+            ]]
+        );
+        assert_eq!(
+            pieces("first.last@mail.example.com", false),
+            [vec!["first.last", "@mail", ".example", ".com"]]
+        );
+        assert_eq!(
+            pieces("first.last@mail.example.com", true),
+            [vec!["first.last", "@mail.example.com"]]
+        );
+        assert_eq!(
+            pieces("/usr/local/bin ../src/main.rs C:\\Users\\me", false),
+            [
+                vec!["/usr/", "local/", "bin"],
+                vec!["../src/", "main.rs"],
+                vec!["C:\\Users\\", "me"]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_overlapping() {
+        let text = "a https://x.io b ~/y/z";
+        let links = TextLinks::new(text, false);
+        assert_eq!(links.overlapping(0, 2), []);
+        assert_eq!(links.overlapping(0, 3), [links.links[0]]);
+        assert_eq!(links.overlapping(10, 22), links.links);
+        assert_eq!(links.containing(13).map(|v| v.start), Some(2));
+        assert_eq!(links.containing(14), None);
+        assert!(links.is_link_start(17));
+    }
+}
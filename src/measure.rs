@@ -0,0 +1,144 @@
+use crate::line::is_line_terminator;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Measures the advance width of a single character.
+///
+/// `Word` and `Line` never look at glyphs themselves, they only sum up what the measurer
+/// reports. Widths are plain integers, so a proportional font can report them in any
+/// fixed unit (pixels, 1/64 pixels, ...) as long as `max_width` uses the same unit.
+pub trait WidthMeasurer {
+    /// Width of `ch`. `tab_width` is the width configured for `\t`.
+    fn char_width(&self, ch: char, tab_width: usize) -> usize;
+
+    /// Width of an extended grapheme cluster, the unit `Word` measures and breaks at.
+    ///
+    /// Defaults to the sum of its characters, override it when clusters are shaped together
+    /// (emoji ZWJ sequences, conjuncts, ...).
+    fn grapheme_width(&self, grapheme: &str, tab_width: usize) -> usize {
+        grapheme
+            .chars()
+            .map(|ch| self.char_width(ch, tab_width))
+            .sum()
+    }
+}
+
+impl<M: WidthMeasurer + ?Sized> WidthMeasurer for &M {
+    fn char_width(&self, ch: char, tab_width: usize) -> usize {
+        (**self).char_width(ch, tab_width)
+    }
+
+    fn grapheme_width(&self, grapheme: &str, tab_width: usize) -> usize {
+        (**self).grapheme_width(grapheme, tab_width)
+    }
+}
+
+/// Width of East Asian Ambiguous characters (UAX #11), such as `“`, `·` or `±`.
+///
+/// Terminals disagree on these, CJK locales usually render them wide.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum AmbiguousWidth {
+    #[default]
+    Narrow,
+    Wide,
+}
+
+/// Default measurer for monospace terminals, driven by the UAX #11 East Asian Width table.
+///
+/// - Wide / Fullwidth: 2 cells
+/// - Narrow / Halfwidth / Neutral: 1 cell
+/// - Ambiguous: decided by [`AmbiguousWidth`]
+/// - combining marks and control characters: 0 cells
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MonospaceMeasurer {
+    ambiguous_width: AmbiguousWidth,
+}
+
+impl MonospaceMeasurer {
+    pub const fn new(ambiguous_width: AmbiguousWidth) -> MonospaceMeasurer {
+        MonospaceMeasurer { ambiguous_width }
+    }
+
+    pub fn ambiguous_width(&self) -> AmbiguousWidth {
+        self.ambiguous_width
+    }
+}
+
+impl WidthMeasurer for MonospaceMeasurer {
+    fn char_width(&self, ch: char, tab_width: usize) -> usize {
+        match ch {
+            '\t' => tab_width,
+            ch if is_line_terminator(ch) => 0,
+            ch => match self.ambiguous_width {
+                AmbiguousWidth::Narrow => ch.width(),
+                AmbiguousWidth::Wide => ch.width_cjk(),
+            }
+            .unwrap_or(0),
+        }
+    }
+
+    fn grapheme_width(&self, grapheme: &str, tab_width: usize) -> usize {
+        if grapheme
+            .chars()
+            .any(|ch| ch.is_control() || is_line_terminator(ch))
+        {
+            return grapheme
+                .chars()
+                .map(|ch| self.char_width(ch, tab_width))
+                .sum();
+        }
+
+        match self.ambiguous_width {
+            AmbiguousWidth::Narrow => grapheme.width(),
+            AmbiguousWidth::Wide => grapheme.width_cjk(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_monospace() {
+        let measurer = MonospaceMeasurer::default();
+        assert_eq!(measurer.char_width('a', 4), 1);
+        assert_eq!(measurer.char_width('中', 4), 2);
+        assert_eq!(measurer.char_width('。', 4), 2);
+        assert_eq!(measurer.char_width('.', 4), 1);
+        assert_eq!(measurer.char_width('\t', 4), 4);
+        assert_eq!(measurer.char_width('\n', 4), 0);
+    }
+
+    #[test]
+    fn test_east_asian_width() {
+        let measurer = MonospaceMeasurer::default();
+        assert_eq!(measurer.char_width('ｱ', 4), 1); // halfwidth katakana
+        assert_eq!(measurer.char_width('Ａ', 4), 2); // fullwidth latin
+        assert_eq!(measurer.char_width('가', 4), 2);
+        assert_eq!(measurer.char_width('\u{0301}', 4), 0); // combining acute accent
+        assert_eq!(measurer.char_width('é', 4), 1);
+        assert_eq!(measurer.char_width('\u{1}', 4), 0);
+    }
+
+    #[test]
+    fn test_ambiguous_width() {
+        let narrow = MonospaceMeasurer::new(AmbiguousWidth::Narrow);
+        let wide = MonospaceMeasurer::new(AmbiguousWidth::Wide);
+        for ch in ['“', '·', '±', '○'] {
+            assert_eq!(narrow.char_width(ch, 4), 1);
+            assert_eq!(wide.char_width(ch, 4), 2);
+        }
+        assert_eq!(wide.char_width('a', 4), 1);
+        assert_eq!(wide.char_width('中', 4), 2);
+    }
+
+    #[test]
+    fn test_grapheme_width() {
+        let measurer = MonospaceMeasurer::default();
+        assert_eq!(measurer.grapheme_width("e\u{301}", 4), 1);
+        assert_eq!(measurer.grapheme_width("\r\n", 4), 0);
+        assert_eq!(measurer.grapheme_width("\t", 4), 4);
+        assert_eq!(measurer.grapheme_width("👨\u{200d}👩\u{200d}👧", 4), 2);
+        assert_eq!(measurer.grapheme_width("", 4), 0);
+    }
+}
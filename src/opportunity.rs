@@ -0,0 +1,119 @@
+//! The places where a line may or may not break, found by the same rules as the wrap modes
+//! of [`TextFlow`](crate::TextFlow) that choose among the segments of a paragraph, but
+//! without fitting them into a width. The greedy heuristic lines fit the words of the text
+//! as they come and may break elsewhere.
+
+use crate::line::Line;
+use crate::measure::{MonospaceMeasurer, WidthMeasurer};
+use crate::TextFlow;
+use std::collections::VecDeque;
+
+/// What a line may do at a [`BreakInfo`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BreakKind {
+    /// The line has to end here: after a line break of the text, or at its end.
+    Mandatory,
+    /// The line may end here.
+    Allowed,
+    /// The line may end here, inside a word, with a hyphen drawn after it.
+    AllowedWithHyphen,
+    /// The line may not end here because of the kinsoku rules, before a closing mark or
+    /// small kana, or after an opening mark, of CJK text.
+    ProhibitedByKinsoku,
+}
+
+/// A break position and the segment of the text before it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BreakInfo {
+    /// Byte offset where the next line would start.
+    pub position: usize,
+    pub kind: BreakKind,
+    /// Width of the text since the previous position, its trailing spaces excluded.
+    pub width: usize,
+    /// Width of the trailing spaces before `position`, which hang at a line end.
+    pub space_width: usize,
+    /// Width of the hyphen drawn when a line ends at a [`BreakKind::AllowedWithHyphen`].
+    pub hyphen_width: usize,
+}
+
+/// Every break position of a text in order, with the widths needed to fit lines on top of
+/// them. The widths of all positions add up to the width of the text.
+pub struct BreakOpportunities<'a, M: WidthMeasurer = MonospaceMeasurer> {
+    lines: Line<'a, M>,
+    start: usize,
+    pending: VecDeque<BreakInfo>,
+}
+
+impl BreakOpportunities<'_> {
+    /// Break opportunities of `text` with the default settings of [`TextFlow`], see
+    /// [`TextFlow::break_opportunities`] for others.
+    pub fn new(text: &str) -> BreakOpportunities<'_> {
+        TextFlow::new(text, usize::MAX).break_opportunities()
+    }
+}
+
+impl<'a, M: WidthMeasurer> BreakOpportunities<'a, M> {
+    pub(crate) fn from_line(lines: Line<'a, M>) -> BreakOpportunities<'a, M> {
+        BreakOpportunities {
+            // long words are not cut, there is no width to cut them at
+            lines: lines.with_long_break(false),
+            start: 0,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl<M: WidthMeasurer> Iterator for BreakOpportunities<'_, M> {
+    type Item = BreakInfo;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pending.is_empty() {
+            self.pending = self.lines.paragraph_opportunities(self.start).into();
+            self.start = self.pending.back()?.position;
+        }
+
+        self.pending.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WhiteSpace;
+
+    #[test]
+    fn test_break_opportunities() {
+        let kinds = |text: &str| -> Vec<(usize, BreakKind)> {
+            BreakOpportunities::new(text)
+                .map(|v| (v.position, v.kind))
+                .collect()
+        };
+        assert_eq!(kinds(""), []);
+        assert_eq!(
+            kinds("ab cd\nef"),
+            [
+                (3, BreakKind::Allowed),
+                (6, BreakKind::Mandatory),
+                (8, BreakKind::Mandatory)
+            ]
+        );
+        // no break before `。`
+        assert_eq!(
+            kinds("漢字。"),
+            [
+                (3, BreakKind::Allowed),
+                (6, BreakKind::ProhibitedByKinsoku),
+                (9, BreakKind::Mandatory)
+            ]
+        );
+
+        // a line break that is a space only allows a break
+        let text = "ab\ncd";
+        let breaks: Vec<_> = TextFlow::new(text, 1)
+            .with_white_space(WhiteSpace::Normal)
+            .break_opportunities()
+            .collect();
+        assert_eq!(breaks[0].kind, BreakKind::Allowed);
+        assert_eq!((breaks[0].width, breaks[0].space_width), (2, 1));
+    }
+}
@@ -0,0 +1,1310 @@
+//! Whole-paragraph line breaking.
+//!
+//! A paragraph is cut into segments, the unbreakable runs between two break opportunities,
+//! and the lines are then chosen over all segments together instead of one line at a time.
+
+use crate::hyphenation::Hyphenator;
+use crate::line::{is_line_terminator, LineBreakStrictness};
+use crate::link::TextLinks;
+use crate::uax14::BreakOpportunity;
+use crate::word::{is_cjk, is_open_punctuation, is_quotation, WordInfo, WordType};
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+
+pub const SOFT_HYPHEN: char = '\u{ad}';
+
+/// An unbreakable run of text, a line may only end after a segment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    pub start: usize,
+    /// end of the visible content, trailing spaces excluded
+    pub end: usize,
+    /// where the next segment starts
+    pub brk: usize,
+    pub width: usize,
+    /// width of the trailing spaces, which hang at a line end
+    pub space_width: usize,
+    /// width of the hyphen drawn when a line ends after this segment, which then ends
+    /// inside a word
+    pub hyphen: Option<usize>,
+    /// width of the punctuation that hangs before the line when it starts with this segment
+    pub hang_start: usize,
+    /// width of the punctuation that may hang after the line when it ends with this segment
+    pub hang_end: usize,
+    /// width taken off an opening mark set half width when a line starts with this segment
+    pub trim_start: usize,
+    /// width taken off a closing mark set half width when a line ends with this segment
+    pub trim_end: usize,
+}
+
+impl Segment {
+    fn new(start: usize) -> Segment {
+        Segment {
+            start,
+            end: start,
+            brk: start,
+            width: 0,
+            space_width: 0,
+            hyphen: None,
+            hang_start: 0,
+            hang_end: 0,
+            trim_start: 0,
+            trim_end: 0,
+        }
+    }
+
+    /// Width taken off a line that starts with this segment, hanging or trimmed.
+    fn start_cut(&self) -> usize {
+        self.hang_start + self.trim_start
+    }
+
+    /// Width taken off a line that ends with this segment, hanging or trimmed.
+    fn end_cut(&self) -> usize {
+        self.hang_end + self.trim_end
+    }
+}
+
+/// The visible part of a line: trailing spaces hang and the line terminator is not shown.
+pub fn trim_line_end(line: &str) -> &str {
+    line.trim_end_matches(is_line_terminator)
+        .trim_end_matches(' ')
+}
+
+/// Break rules of the heuristic engine between two adjacent words, in the wrap modes that
+/// choose among the segments of a paragraph.
+///
+/// Quotation marks alternate between opening and closing: no break after an opening one,
+/// nor before a closing one.
+fn can_break_between(prev: &WordInfo, next: &WordInfo, quotation_open: bool) -> bool {
+    match next.word_type {
+        WordType::CLOSE_PUNCTUATION
+        | WordType::HYPHEN
+        | WordType::SOFT_HYPHEN
+        | WordType::NEWLINE
+        | WordType::RETURN
+        | WordType::SPACE => return false,
+        WordType::QUOTATION if quotation_open => return false,
+        _ => {}
+    }
+
+    match prev.word_type {
+        // a soft hyphen outside a word has nothing to hyphenate
+        WordType::OPEN_PUNCTUATION | WordType::SOFT_HYPHEN => false,
+        WordType::QUOTATION => !quotation_open,
+        _ => true,
+    }
+}
+
+/// Segments of the paragraph made of `words`, the words of the text from `start` on,
+/// following the heuristic engine rules.
+///
+/// The paragraph ends after the first `\n` or `\r`, which hangs after the last segment.
+pub fn word_segments(start: usize, words: impl Iterator<Item = WordInfo>) -> Vec<Segment> {
+    let mut segments = vec![];
+    let mut segment = Segment::new(start);
+    let mut prev: Option<WordInfo> = None;
+    let mut quotation_open = false;
+    // nothing but spaces so far
+    let mut blank = true;
+
+    for word in words {
+        let word_start = start + word.position.start;
+        let word_end = start + word.position.end;
+        let is_paragraph_end =
+            word.word_type == WordType::NEWLINE || word.word_type == WordType::RETURN;
+
+        if is_paragraph_end {
+            segment.brk = word_end;
+        } else if word.word_type == WordType::SPACE
+            && segment.end > segment.start
+            && word.ideal_width > 0
+        {
+            segment.brk = word_end;
+            segment.space_width += word.ideal_width;
+        } else {
+            if let Some(prev) = prev.as_ref() {
+                if segment.end > segment.start && can_break_between(prev, &word, quotation_open) {
+                    segments.push(segment);
+                    segment = Segment::new(word_start);
+                }
+            }
+
+            // spaces inside a segment do not hang, nor do zero width spaces, which a line
+            // still breaks after
+            segment.width += segment.space_width + word.ideal_width;
+            segment.space_width = 0;
+            segment.end = word_end;
+            segment.brk = word_end;
+            blank &= word.word_type == WordType::SPACE;
+        }
+
+        if word.word_type == WordType::QUOTATION {
+            quotation_open = !quotation_open;
+        }
+        prev = Some(word);
+
+        if is_paragraph_end {
+            break;
+        }
+    }
+
+    if segment.brk > segment.start {
+        // a paragraph of spaces only has nothing to show, they hang
+        if blank {
+            segment.space_width += segment.width;
+            segment.width = 0;
+            segment.end = segment.start;
+        }
+        segments.push(segment);
+    }
+    segments
+}
+
+/// Segments of the paragraph at `text[start..]` between the UAX #14 break opportunities,
+/// `measure` gets a range of `text` and the column it starts at.
+///
+/// The paragraph ends at the first mandatory break.
+pub fn uax14_segments(
+    text: &str,
+    start: usize,
+    breaks: &[(usize, BreakOpportunity)],
+    measure: impl Fn(Range<usize>, usize) -> usize,
+) -> Vec<Segment> {
+    let mut segments = vec![];
+    let mut segment_start = start;
+    // tabs are measured from the start of the paragraph until the lines are known
+    let mut column = 0;
+
+    for &(pos, opportunity) in breaks.iter().skip_while(|v| v.0 <= start) {
+        let end = segment_start + trim_line_end(&text[segment_start..pos]).len();
+        let width = measure(segment_start..end, column);
+        let space_width = measure(end..pos, column + width);
+        column += width + space_width;
+        segments.push(Segment {
+            start: segment_start,
+            end,
+            brk: pos,
+            width,
+            space_width,
+            hyphen: None,
+            hang_start: 0,
+            hang_end: 0,
+            trim_start: 0,
+            trim_end: 0,
+        });
+        segment_start = pos;
+
+        if opportunity == BreakOpportunity::Mandatory {
+            break;
+        }
+    }
+
+    segments
+}
+
+/// Collapsible white space at the end of a segment hangs like its trailing spaces, for the
+/// tabs and line breaks that UAX #14 keeps inside segments. A segment of white space only
+/// joins the one before it.
+pub fn collapse_segments(
+    text: &str,
+    segments: Vec<Segment>,
+    is_collapsible: impl Fn(&str) -> bool,
+    measure: impl Fn(&str) -> usize,
+) -> Vec<Segment> {
+    let mut result: Vec<Segment> = Vec::with_capacity(segments.len());
+
+    for mut segment in segments {
+        let content = &text[segment.start..segment.end];
+        let end = segment.start
+            + content
+                .grapheme_indices(true)
+                .rev()
+                .take_while(|v| is_collapsible(v.1))
+                .last()
+                .map_or(content.len(), |v| v.0);
+
+        match result.last_mut() {
+            Some(prev) if end == segment.start => {
+                prev.brk = segment.brk;
+                prev.space_width = measure(&text[prev.end..prev.brk]);
+            }
+            _ => {
+                if end < segment.end {
+                    segment.width = measure(&text[segment.start..end]);
+                    segment.space_width = measure(&text[end..segment.brk]);
+                    segment.end = end;
+                }
+                result.push(segment);
+            }
+        }
+    }
+    result
+}
+
+/// Spaces that take their width at a line end and a break after every one of them, for
+/// `white-space: break-spaces`: the first trailing space of a segment joins it, every other
+/// one becomes a segment of its own.
+pub fn break_spaces_segments(
+    text: &str,
+    segments: Vec<Segment>,
+    measure: impl Fn(&str) -> usize,
+) -> Vec<Segment> {
+    let mut result = Vec::with_capacity(segments.len());
+
+    for mut segment in segments {
+        let spaces = &text[segment.end..segment.brk];
+        let spaces = &spaces[..spaces.len() - spaces.trim_start_matches(' ').len()];
+        if spaces.is_empty() {
+            result.push(segment);
+            continue;
+        }
+
+        let brk = segment.brk;
+        let space_end = segment.end + spaces.len();
+        segment.width += measure(" ");
+        segment.end += 1;
+        segment.space_width = 0;
+        segment.brk = segment.end;
+        result.push(segment);
+        for pos in result.last().map_or(0, |v| v.end)..space_end {
+            let mut space = Segment::new(pos);
+            space.width = measure(" ");
+            space.end = pos + 1;
+            space.brk = pos + 1;
+            result.push(space);
+        }
+        // the line terminator, if any, still hangs
+        if let Some(last) = result.last_mut() {
+            last.brk = brk;
+        }
+    }
+    result
+}
+
+/// Cut segments wider than `max_width` at grapheme boundaries, for `long_break`: at the last
+/// one that fits where `can_cut` allows a cut between the graphemes before and after it, or
+/// right before the grapheme that overflows when none does. `measure` gives the width of a
+/// range of `text` starting at a column of the paragraph.
+pub fn split_long_segments(
+    text: &str,
+    segments: Vec<Segment>,
+    max_width: usize,
+    measure: impl Fn(Range<usize>, usize) -> usize,
+    can_cut: impl Fn(&str, &str) -> bool,
+) -> Vec<Segment> {
+    let mut result = Vec::with_capacity(segments.len());
+    let columns = segment_columns(&segments);
+
+    for (segment, column) in segments.into_iter().zip(columns) {
+        let graphemes: Vec<_> = text[segment.start..segment.end]
+            .grapheme_indices(true)
+            .collect();
+        // a single grapheme cannot be cut
+        if segment.width <= max_width || graphemes.len() < 2 {
+            result.push(segment);
+            continue;
+        }
+
+        let mut piece = Segment::new(segment.start);
+        // where the piece starts in the paragraph, tabs advance from there
+        let mut piece_column = column;
+        // the grapheme after the last allowed cut, and the piece that ends there
+        let mut last_cut: Option<(usize, Segment)> = None;
+        let mut i = 0;
+        while i < graphemes.len() {
+            let (offset, grapheme) = graphemes[i];
+            let grapheme_start = segment.start + offset;
+            let grapheme_end = grapheme_start + grapheme.len();
+            let grapheme_column = piece_column + piece.width + piece.space_width;
+            let width = measure(grapheme_start..grapheme_end, grapheme_column);
+            // spaces after the content of a piece hang when it is cut after them
+            if grapheme == " " && piece.end > piece.start {
+                piece.space_width += width;
+                piece.brk = grapheme_end;
+                i += 1;
+                continue;
+            }
+            if piece.end > piece.start {
+                if can_cut(graphemes[i - 1].1, grapheme) {
+                    last_cut = Some((i, piece.clone()));
+                }
+                if piece.width + piece.space_width + width > max_width {
+                    match last_cut.take() {
+                        Some((cut, cut_piece)) => {
+                            piece_column += cut_piece.width + cut_piece.space_width;
+                            result.push(cut_piece);
+                            piece = Segment::new(segment.start + graphemes[cut].0);
+                            i = cut;
+                            continue;
+                        }
+                        None => {
+                            piece_column = grapheme_column;
+                            result.push(piece);
+                            piece = Segment::new(grapheme_start);
+                        }
+                    }
+                }
+            }
+            piece.width += piece.space_width + width;
+            piece.space_width = 0;
+            piece.end = grapheme_end;
+            piece.brk = piece.end;
+            i += 1;
+        }
+        piece.brk = segment.brk;
+        piece.space_width += segment.space_width;
+        piece.hyphen = segment.hyphen;
+        result.push(piece);
+    }
+
+    result
+}
+
+/// A segment that is too long may be cut between the graphemes `prev` and `next` by the
+/// rules of the heuristic engine: punctuation stays with the word it belongs to.
+pub fn can_cut_between(prev: &str, next: &str, strictness: LineBreakStrictness) -> bool {
+    let prev = WordType::classify(prev, strictness);
+    let next = WordType::classify(next, strictness);
+    !matches!(
+        next,
+        WordType::CLOSE_PUNCTUATION
+            | WordType::HYPHEN
+            | WordType::SOFT_HYPHEN
+            | WordType::NEWLINE
+            | WordType::RETURN
+            | WordType::SPACE
+    ) && !matches!(prev, WordType::OPEN_PUNCTUATION | WordType::SOFT_HYPHEN)
+}
+
+/// Cut segments at the hyphenation points of the words in them, words of `links` are not
+/// hyphenated.
+pub fn hyphenate_segments(
+    text: &str,
+    segments: Vec<Segment>,
+    hyphenator: &Hyphenator,
+    links: &TextLinks,
+    hyphen_width: usize,
+    measure: impl Fn(&str) -> usize,
+) -> Vec<Segment> {
+    let mut result = Vec::with_capacity(segments.len());
+
+    for segment in segments {
+        let mut piece_start = segment.start;
+        for (offset, word) in text[segment.start..segment.end].split_word_bound_indices() {
+            // words with soft hyphens already say where they may break
+            if !word.starts_with(char::is_alphabetic)
+                || word.contains(SOFT_HYPHEN)
+                || links.containing(segment.start + offset).is_some()
+            {
+                continue;
+            }
+
+            for hyphen in hyphenator.hyphenate(word) {
+                let piece_end = segment.start + offset + hyphen;
+                result.push(Segment {
+                    start: piece_start,
+                    end: piece_end,
+                    brk: piece_end,
+                    width: measure(&text[piece_start..piece_end]),
+                    space_width: 0,
+                    hyphen: Some(hyphen_width),
+                    hang_start: 0,
+                    hang_end: 0,
+                    trim_start: 0,
+                    trim_end: 0,
+                });
+                piece_start = piece_end;
+            }
+        }
+
+        if piece_start == segment.start {
+            result.push(segment);
+        } else {
+            result.push(Segment {
+                start: piece_start,
+                width: measure(&text[piece_start..segment.end]),
+                ..segment
+            });
+        }
+    }
+
+    result
+}
+
+/// Cut segments after their soft hyphens, which are only shown when a line ends there.
+///
+/// A soft hyphen only offers a break after word content: one that starts a segment stays
+/// in it, and one after a space leaves a plain break at that space, where it hangs when
+/// nothing follows it in its segment.
+pub fn soft_hyphen_segments(
+    text: &str,
+    segments: Vec<Segment>,
+    hyphen_width: usize,
+    measure: impl Fn(&str) -> usize,
+) -> Vec<Segment> {
+    let mut result = Vec::with_capacity(segments.len());
+
+    for segment in segments {
+        let first_piece = result.len();
+        let mut piece_start = segment.start;
+        for (offset, _) in text[segment.start..segment.end].match_indices(SOFT_HYPHEN) {
+            let pos = segment.start + offset;
+            let before = &text[piece_start..pos];
+            let content = before.trim_end_matches(' ');
+            if content.trim_start_matches(' ').is_empty() {
+                continue;
+            }
+
+            let end = piece_start + content.len();
+            let (brk, hyphen) = if end < pos {
+                (pos, None)
+            } else {
+                (pos + SOFT_HYPHEN.len_utf8(), Some(hyphen_width))
+            };
+            result.push(Segment {
+                start: piece_start,
+                end,
+                brk,
+                width: measure(content),
+                space_width: measure(&text[end..pos]),
+                hyphen,
+                hang_start: 0,
+                hang_end: 0,
+                trim_start: 0,
+                trim_end: 0,
+            });
+            piece_start = brk;
+        }
+
+        let rest = &text[piece_start..segment.end];
+        match result.last_mut() {
+            // soft hyphens alone after a plain break are not shown, they hang after it
+            Some(prev)
+                if prev.hyphen.is_none()
+                    && !rest.is_empty()
+                    && rest.trim_start_matches(SOFT_HYPHEN).is_empty() =>
+            {
+                prev.brk = segment.brk;
+                prev.space_width += segment.space_width;
+                continue;
+            }
+            _ => {}
+        }
+
+        if piece_start < segment.end {
+            result.push(Segment {
+                start: piece_start,
+                width: measure(rest),
+                ..segment
+            });
+        } else if result.len() > first_piece {
+            // the segment ends with a soft hyphen, as UAX #14 cuts them; a line that ends at
+            // the spaces or the line break after it shows no hyphen
+            let last = result.last_mut().unwrap();
+            if segment.brk > segment.end {
+                last.hyphen = segment.hyphen;
+            }
+            last.brk = segment.brk;
+            last.space_width = segment.space_width;
+        } else {
+            result.push(segment);
+        }
+    }
+
+    result
+}
+
+/// Punctuation that may hang outside the line (CSS `hanging-punctuation`), nothing hangs by
+/// default. A hanging mark is still part of the line, but not of the `max_width` it fits in.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct HangingPunctuation {
+    /// An opening bracket or quote at the start of a paragraph hangs before its first line.
+    pub first: bool,
+    /// A closing bracket or quote at the end of a paragraph may hang after its last line.
+    pub last: bool,
+    /// A stop or comma, such as `。`, `，` or `.`, may hang after any line.
+    pub allow_end: bool,
+}
+
+fn is_hanging_stop(ch: char) -> bool {
+    matches!(
+        ch,
+        ',' | '.' | '،' | '۔' | '、' | '。' | '，' | '．' | '﹐' | '﹑' | '﹒' | '｡' | '､'
+    )
+}
+
+fn is_hanging_open(ch: char) -> bool {
+    is_open_punctuation(ch) || is_quotation(ch) || matches!(ch, '«' | '‹' | '„' | '‚')
+}
+
+fn is_hanging_close(ch: char) -> bool {
+    is_quotation(ch)
+        || matches!(
+            ch,
+            ')' | ']'
+                | '}'
+                | '）'
+                | '」'
+                | '』'
+                | '】'
+                | '〕'
+                | '〉'
+                | '》'
+                | '⦘'
+                | '⟩'
+                | '’'
+                | '”'
+                | '»'
+                | '›'
+        )
+}
+
+/// Set the width of the punctuation that may hang at the start and the end of the segments
+/// of a paragraph.
+pub fn hang_segments(
+    text: &str,
+    mut segments: Vec<Segment>,
+    hanging: &HangingPunctuation,
+    measure: impl Fn(&str) -> usize,
+) -> Vec<Segment> {
+    let n = segments.len();
+    for (i, segment) in segments.iter_mut().enumerate() {
+        let mut graphemes = text[segment.start..segment.end].graphemes(true);
+        let first = graphemes.next();
+        let last = graphemes.next_back().or(first);
+
+        if let Some(first) =
+            first.filter(|v| hanging.first && i == 0 && v.starts_with(is_hanging_open))
+        {
+            segment.hang_start = measure(first).saturating_sub(segment.trim_start);
+        }
+        let is_last = i + 1 == n;
+        if let Some(last) = last.filter(|v| {
+            (hanging.allow_end && v.starts_with(is_hanging_stop))
+                || (hanging.last && is_last && v.starts_with(is_hanging_close))
+        }) {
+            segment.hang_end = measure(last).saturating_sub(segment.trim_end);
+        }
+    }
+    segments
+}
+
+/// Compression of fullwidth CJK punctuation (CSS `text-spacing-trim`), none by default.
+///
+/// A fullwidth bracket or stop is drawn on half of its cell with a blank half beside it;
+/// a trimmed mark is set half width without that blank.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SpacingTrim {
+    /// Every mark keeps its full width.
+    #[default]
+    SpaceAll,
+    /// An opening mark after an opening one or a middle dot, a closing mark before a closing
+    /// one or a middle dot, and a closing mark at a line end are set half width, such as
+    /// `「「`, `。」` and `、` ending a line.
+    Normal,
+    /// [`SpacingTrim::Normal`], and an opening mark at a line start is set half width too.
+    TrimStart,
+}
+
+fn is_fullwidth_open(ch: char) -> bool {
+    matches!(
+        ch,
+        '（' | '［' | '｛' | '｟' | '「' | '『' | '【' | '〔' | '〈' | '《' | '〖' | '〘' | '〚'
+    )
+}
+
+fn is_fullwidth_close(ch: char) -> bool {
+    matches!(
+        ch,
+        '）' | '］'
+            | '｝'
+            | '｠'
+            | '」'
+            | '』'
+            | '】'
+            | '〕'
+            | '〉'
+            | '》'
+            | '〗'
+            | '〙'
+            | '〛'
+            | '、'
+            | '。'
+            | '，'
+            | '．'
+    )
+}
+
+fn is_fullwidth_middle(ch: char) -> bool {
+    matches!(ch, '・' | '：' | '；')
+}
+
+/// Whether the mark `ch` is set half width by the marks next to it on its blank side.
+fn is_trimmed_adjacent(prev: Option<char>, ch: char, next: Option<char>) -> bool {
+    (is_fullwidth_open(ch)
+        && prev.is_some_and(|v| is_fullwidth_open(v) || is_fullwidth_middle(v) || v == '\u{3000}'))
+        || (is_fullwidth_close(ch)
+            && next.is_some_and(|v| is_fullwidth_close(v) || is_fullwidth_middle(v)))
+}
+
+/// Set fullwidth punctuation of the segments of a paragraph half width: adjacent marks inside
+/// a segment are taken off its width, marks at its start and end are only trimmed when a
+/// line starts or ends with it.
+pub fn trim_segments(
+    text: &str,
+    mut segments: Vec<Segment>,
+    trim: SpacingTrim,
+    measure: impl Fn(&str) -> usize,
+) -> Vec<Segment> {
+    if trim == SpacingTrim::SpaceAll {
+        return segments;
+    }
+
+    for segment in segments.iter_mut() {
+        let graphemes: Vec<_> = text[segment.start..segment.end].graphemes(true).collect();
+        let first_char = |i: usize| graphemes.get(i).and_then(|v| v.chars().next());
+        let mut trimmed = 0;
+        for (i, grapheme) in graphemes.iter().enumerate() {
+            let ch = first_char(i).unwrap_or_default();
+            let prev = i.checked_sub(1).and_then(first_char);
+            if is_trimmed_adjacent(prev, ch, first_char(i + 1)) {
+                trimmed += measure(grapheme) / 2;
+            }
+        }
+        segment.width = segment.width.saturating_sub(trimmed);
+
+        if let Some(first) = graphemes
+            .first()
+            .filter(|v| trim == SpacingTrim::TrimStart && v.starts_with(is_fullwidth_open))
+        {
+            segment.trim_start = measure(first) / 2;
+        }
+        // the neighbours of the first and last marks are in other segments
+        if let Some(last) = graphemes
+            .last()
+            .filter(|v| v.starts_with(is_fullwidth_close))
+        {
+            segment.trim_end = measure(last) / 2;
+        }
+    }
+    segments
+}
+
+/// Han ideographs, kana and bopomofo, the CJK punctuation excluded.
+fn is_ideograph(ch: char) -> bool {
+    matches!(
+        ch as u32,
+        0x2E80..=0x2FDF // cjk radicals supplement, kangxi radicals
+            | 0x3005..=0x3007 // ideographic iteration mark, closing mark, number zero
+            | 0x3021..=0x3029 // hangzhou numerals
+            | 0x3031..=0x3035 // vertical kana repeat marks
+            | 0x3038..=0x303C
+            | 0x3041..=0x3096 // hiragana
+            | 0x309D..=0x309F
+            | 0x30A1..=0x30FA // katakana
+            | 0x30FC..=0x30FF
+            | 0x3105..=0x312F // bopomofo
+            | 0x31A0..=0x31BF // bopomofo extended
+            | 0x31F0..=0x31FF // katakana phonetic extensions
+            | 0x3400..=0x4DBF // cjk unified ideographs extension a
+            | 0x4E00..=0x9FFF // cjk unified ideographs
+            | 0xF900..=0xFAFF // cjk compatibility ideographs
+            | 0xFF66..=0xFF9F // halfwidth katakana
+            | 0x1AFF0..=0x1B16F // kana extended-b, kana supplement, kana extended-a, small kana extension
+            | 0x20000..=0x323AF // cjk unified ideographs extension b - h, compatibility supplement
+    )
+}
+
+/// Letters and digits of the scripts that are not CJK.
+fn is_alphanumeric(ch: char) -> bool {
+    ch.is_alphanumeric() && !is_cjk(ch) && !matches!(ch as u32, 0xFF00..=0xFFEF)
+}
+
+/// Whether autospace puts a gap between two adjacent graphemes, an ideograph on one side and
+/// a letter or digit on the other (CSS `text-autospace`).
+fn is_autospace_between(prev: &str, next: &str) -> bool {
+    let (Some(prev), Some(next)) = (prev.chars().next(), next.chars().next()) else {
+        return false;
+    };
+    (is_ideograph(prev) && is_alphanumeric(next)) || (is_alphanumeric(prev) && is_ideograph(next))
+}
+
+/// Byte offsets in `text` where autospace puts a gap before the grapheme there.
+pub fn autospace_positions(text: &str) -> Vec<usize> {
+    let mut positions = vec![];
+    let mut graphemes = text.grapheme_indices(true).peekable();
+    while let Some((_, grapheme)) = graphemes.next() {
+        if let Some(&(pos, next)) = graphemes.peek() {
+            if is_autospace_between(grapheme, next) {
+                positions.push(pos);
+            }
+        }
+    }
+    positions
+}
+
+/// Add the autospace `gap` to the segments of a paragraph: a gap inside a segment widens it,
+/// a gap between two segments hangs at a line end like a trailing space.
+pub fn autospace_segments(text: &str, mut segments: Vec<Segment>, gap: usize) -> Vec<Segment> {
+    if gap == 0 {
+        return segments;
+    }
+
+    for i in 0..segments.len() {
+        let segment = &segments[i];
+        let inside = autospace_positions(&text[segment.start..segment.end]).len();
+        let between = segment.end == segment.brk
+            && segments.get(i + 1).is_some_and(|next| {
+                let prev = text[segment.start..segment.end].graphemes(true).next_back();
+                let next = text[next.start..next.end].graphemes(true).next();
+                prev.zip(next)
+                    .is_some_and(|(a, b)| is_autospace_between(a, b))
+            });
+
+        let segment = &mut segments[i];
+        segment.width += inside * gap;
+        if between {
+            segment.space_width += gap;
+        }
+    }
+    segments
+}
+
+/// Where each of `segments` starts in its paragraph, the column its tabs were measured from.
+pub fn segment_columns(segments: &[Segment]) -> Vec<usize> {
+    segments
+        .iter()
+        .scan(0, |column, v| {
+            let start = *column;
+            *column += v.width + v.space_width;
+            Some(start)
+        })
+        .collect()
+}
+
+/// Width of `segments` set on one line, the trailing spaces of the last one hang and its
+/// hyphen, if any, is drawn.
+pub fn line_width(segments: &[Segment]) -> usize {
+    let Some(last) = segments.last() else {
+        return 0;
+    };
+
+    segments
+        .iter()
+        .map(|v| v.width + v.space_width)
+        .sum::<usize>()
+        - last.space_width
+        + last.hyphen.unwrap_or(0)
+        - segments[0].trim_start
+        - last.trim_end
+}
+
+/// Width of `segments` on one line that has to fit in `max_width`: [`line_width`] without
+/// its hanging punctuation.
+pub fn fit_width(segments: &[Segment]) -> usize {
+    let (Some(first), Some(last)) = (segments.first(), segments.last()) else {
+        return 0;
+    };
+    line_width(segments).saturating_sub(first.hang_start + last.hang_end)
+}
+
+/// First fit: every line takes as many segments as fit in `max_width`.
+///
+/// Returns, for every line, the index one past its last segment.
+pub fn greedy_breaks(segments: &[Segment], max_width: usize) -> Vec<usize> {
+    let mut breaks = vec![];
+    // width of the current line, trailing spaces included, and what hangs or is trimmed
+    // before it
+    let mut width = 0;
+    let mut hang_start = segments.first().map_or(0, Segment::start_cut);
+
+    for (i, segment) in segments.iter().enumerate() {
+        let end = width + segment.width + segment.hyphen.unwrap_or(0);
+        if width > 0 && end.saturating_sub(hang_start + segment.end_cut()) > max_width {
+            breaks.push(i);
+            width = 0;
+            hang_start = segment.start_cut();
+        }
+        width += segment.width + segment.space_width;
+    }
+    if !segments.is_empty() {
+        breaks.push(segments.len());
+    }
+    breaks
+}
+
+/// Greedy breaks at the narrowest width that still gives as many lines as `max_width` does,
+/// so that the lines come out about the same length.
+pub fn balanced_breaks(segments: &[Segment], max_width: usize) -> Vec<usize> {
+    let breaks = greedy_breaks(segments, max_width);
+    if breaks.len() < 2 {
+        return breaks;
+    }
+
+    // no line can be narrower than its widest segment, and the line count of first fit
+    // never grows with the width
+    let mut low = segments.iter().map(|v| v.width).max().unwrap_or(0);
+    let mut high = max_width;
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if greedy_breaks(segments, mid).len() > breaks.len() {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+
+    if low < max_width {
+        greedy_breaks(segments, low)
+    } else {
+        breaks
+    }
+}
+
+/// What the last line of a paragraph needs in `WrapMode::Pretty` to not count as an orphan.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrphanControl {
+    /// Minimum number of words, where each CJK character counts as a word.
+    pub min_words: usize,
+    /// Minimum width as a fraction of `max_width`.
+    pub min_fill: f32,
+    /// How many lines before the last one may be rewrapped to fix it.
+    pub look_back: usize,
+}
+
+impl Default for OrphanControl {
+    fn default() -> Self {
+        OrphanControl {
+            min_words: 2,
+            min_fill: 0.0,
+            look_back: 3,
+        }
+    }
+}
+
+/// Whether a word ends with each of the `segments` of a paragraph of `text`: at white space,
+/// at the end of the paragraph, and around every CJK character. A segment cut at a
+/// hyphenation point or after a hyphen does not end a word.
+fn word_ends(text: &str, segments: &[Segment]) -> Vec<bool> {
+    let is_cjk_letter = |ch: char| is_cjk(ch) && ch.is_alphanumeric();
+    segments
+        .iter()
+        .enumerate()
+        .map(|(i, segment)| {
+            let next = segments.get(i + 1).map(|v| &text[v.start..v.end]);
+            next.is_none()
+                || (segment.brk > segment.end && segment.hyphen.is_none())
+                || text[segment.start..segment.end]
+                    .chars()
+                    .next_back()
+                    .is_some_and(is_cjk_letter)
+                || next
+                    .and_then(|v| v.chars().next())
+                    .is_some_and(is_cjk_letter)
+        })
+        .collect()
+}
+
+/// Greedy breaks, except that the last lines are rewrapped when the last one would be an
+/// orphan. The line count stays the same, and the moved breaks are still break opportunities.
+pub fn pretty_breaks(
+    text: &str,
+    segments: &[Segment],
+    max_width: usize,
+    orphans: &OrphanControl,
+) -> Vec<usize> {
+    let mut breaks = greedy_breaks(segments, max_width);
+    let lines = breaks.len();
+    let n = segments.len();
+
+    // words_from[i]: number of words in `segments[i..]`
+    let mut words_from = vec![0; n + 1];
+    for (i, &word_end) in word_ends(text, segments).iter().enumerate().rev() {
+        words_from[i] = words_from[i + 1] + usize::from(word_end);
+    }
+
+    let fits = |i: usize, j: usize| i + 1 == j || fit_width(&segments[i..j]) <= max_width;
+    let is_good_last_line = |i: usize| {
+        words_from[i] >= orphans.min_words
+            && line_width(&segments[i..]) as f32 >= orphans.min_fill * max_width as f32
+    };
+
+    if lines < 2 || is_good_last_line(breaks[lines - 2]) {
+        return breaks;
+    }
+
+    // the last `look_back` lines before the last one are rewrapped, earlier ones are kept
+    let look_back = orphans.look_back.clamp(1, lines - 1);
+    let first_line = lines - 1 - look_back;
+    let region_start = if first_line == 0 {
+        0
+    } else {
+        breaks[first_line - 1]
+    };
+
+    // best[l][j]: least squared slack of `segments[region_start..j]` set in `l` lines,
+    // and where the last of those lines starts
+    let mut best = vec![vec![(f64::INFINITY, 0); n + 1]; look_back + 1];
+    best[0][region_start] = (0.0, region_start);
+    for l in 1..=look_back {
+        for j in region_start + 1..=n {
+            for i in (region_start..j).rev() {
+                if !fits(i, j) {
+                    break;
+                }
+                let slack = max_width.saturating_sub(fit_width(&segments[i..j])) as f64;
+                let total = best[l - 1][i].0 + slack * slack;
+                if total < best[l][j].0 {
+                    best[l][j] = (total, i);
+                }
+            }
+        }
+    }
+
+    let last_line_start = (region_start + 1..n)
+        .filter(|&i| best[look_back][i].0.is_finite() && fits(i, n) && is_good_last_line(i))
+        .min_by(|&a, &b| best[look_back][a].0.total_cmp(&best[look_back][b].0));
+    let Some(mut j) = last_line_start else {
+        // nothing better within reach, keep the orphan
+        return breaks;
+    };
+
+    breaks.truncate(first_line);
+    let mut region = vec![n];
+    for l in (1..=look_back).rev() {
+        region.push(j);
+        j = best[l][j].1;
+    }
+    breaks.extend(region.into_iter().rev());
+    breaks
+}
+
+const LINE_PENALTY: f64 = 10.0;
+const HYPHEN_PENALTY: f64 = 50.0;
+const OVERFLOW_PENALTY: f64 = 1e8;
+
+/// Knuth–Plass style demerits of a line `width` wide.
+fn demerits(width: usize, max_width: usize, is_last: bool, is_hyphenated: bool) -> f64 {
+    if width > max_width {
+        return OVERFLOW_PENALTY * (width - max_width) as f64;
+    }
+
+    // the last line of a paragraph may be as short as it likes
+    let badness = if is_last || max_width == 0 {
+        0.0
+    } else {
+        100.0 * ((max_width - width) as f64 / max_width as f64).powi(3)
+    };
+    let penalty = if is_hyphenated { HYPHEN_PENALTY } else { 0.0 };
+    (LINE_PENALTY + badness).powi(2) + penalty.powi(2)
+}
+
+/// Choose the line ends that minimise the total demerits of the paragraph.
+///
+/// Returns, for every line, the index one past its last segment.
+pub fn optimal_breaks(segments: &[Segment], max_width: usize) -> Vec<usize> {
+    let n = segments.len();
+
+    // offsets[i]: width of segments[..i] including their trailing spaces
+    let mut offsets = Vec::with_capacity(n + 1);
+    offsets.push(0);
+    for segment in segments {
+        offsets.push(offsets.last().unwrap() + segment.width + segment.space_width);
+    }
+
+    let mut best = vec![(f64::INFINITY, 0); n + 1];
+    best[0] = (0.0, 0);
+
+    for j in 1..=n {
+        for i in (0..j).rev() {
+            let last = &segments[j - 1];
+            let width = (offsets[j] - offsets[i] - last.space_width + last.hyphen.unwrap_or(0))
+                .saturating_sub(segments[i].start_cut() + last.end_cut());
+            // a line with more than one segment can only get worse from here
+            if width > max_width && i + 1 < j {
+                break;
+            }
+
+            let total = best[i].0 + demerits(width, max_width, j == n, last.hyphen.is_some());
+            if total < best[j].0 {
+                best[j] = (total, i);
+            }
+        }
+    }
+
+    let mut breaks = vec![];
+    let mut j = n;
+    while j > 0 {
+        breaks.push(j);
+        j = best[j].1;
+    }
+    breaks.reverse();
+    breaks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::word::Word;
+
+    fn lines(
+        text: &str,
+        max_width: usize,
+        breaks: impl Fn(&[Segment], usize) -> Vec<usize>,
+    ) -> Vec<&str> {
+        let segments = word_segments(0, Word::new(text, usize::MAX, 4, 0));
+        let mut start = 0;
+        breaks(&segments, max_width)
+            .into_iter()
+            .map(|end| {
+                let line = &text[segments[start].start..segments[end - 1].end];
+                start = end;
+                line
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_word_segments() {
+        let text = "a \"quoted\" text-test 《你好》。";
+        let segments = word_segments(0, Word::new(text, usize::MAX, 4, 0));
+        let slices: Vec<_> = segments.iter().map(|v| &text[v.start..v.end]).collect();
+        assert_eq!(
+            slices,
+            ["a", "\"quoted\"", "text-", "test", "《你", "好》。"]
+        );
+        assert_eq!(segments[0].space_width, 1);
+        assert_eq!(segments[1].width, 8);
+    }
+
+    #[test]
+    fn test_optimal_breaks() {
+        // greedy fitting gives "aaa bb" / "cc" / "ddddd"
+        assert_eq!(
+            lines("aaa bb cc ddddd", 6, optimal_breaks),
+            ["aaa", "bb cc", "ddddd"]
+        );
+        assert_eq!(lines("", 6, optimal_breaks), Vec::<&str>::new());
+        assert_eq!(lines("aaaaaaaa b", 4, optimal_breaks), ["aaaaaaaa", "b"]);
+        assert_eq!(lines("ab  \ncd", 6, optimal_breaks), ["ab"]);
+    }
+
+    #[test]
+    fn test_split_long_segments() {
+        let measure = |range: Range<usize>, _| range.len();
+        let can_cut =
+            |prev: &str, next: &str| can_cut_between(prev, next, LineBreakStrictness::Normal);
+        let text = "foo-bar (abc)";
+        let segments = word_segments(0, Word::new(text, usize::MAX, 4, 0));
+        let segments = split_long_segments(text, segments, 3, measure, can_cut);
+        let slices: Vec<_> = segments.iter().map(|v| &text[v.start..v.end]).collect();
+        // the hyphen and the brackets stay with a letter
+        assert_eq!(slices, ["fo", "o-", "bar", "(ab", "c)"]);
+        assert_eq!(segments[2].space_width, 1);
+
+        // without an allowed cut that fits, the overflowing grapheme starts the next piece
+        let text = "(((((";
+        let segments = word_segments(0, Word::new(text, usize::MAX, 4, 0));
+        let segments = split_long_segments(text, segments, 2, measure, can_cut);
+        let slices: Vec<_> = segments.iter().map(|v| &text[v.start..v.end]).collect();
+        assert_eq!(slices, ["((", "((", "("]);
+    }
+
+    #[test]
+    fn test_soft_hyphen_segments() {
+        let measure = |v: &str| v.chars().filter(|&ch| ch != SOFT_HYPHEN).count();
+        let text = "hy\u{ad}phen\u{ad} ab";
+        let segments = word_segments(0, Word::new(text, usize::MAX, 4, 0));
+        let segments = soft_hyphen_segments(text, segments, 1, measure);
+        let slices: Vec<_> = segments.iter().map(|v| &text[v.start..v.end]).collect();
+        assert_eq!(slices, ["hy", "phen", "ab"]);
+        assert_eq!(segments[0].brk, 4);
+        assert_eq!(segments[0].hyphen, Some(1));
+        assert_eq!((segments[1].brk, segments[1].space_width), (11, 1));
+        assert_eq!(segments[1].hyphen, None);
+        assert_eq!(segments[2].hyphen, None);
+
+        // no break at a soft hyphen without word content before it
+        let text = "\u{ad}ab \u{ad}cd";
+        let segments = word_segments(0, Word::new(text, usize::MAX, 4, 0));
+        let segments = soft_hyphen_segments(text, segments, 1, measure);
+        let slices: Vec<_> = segments.iter().map(|v| &text[v.start..v.end]).collect();
+        assert_eq!(slices, ["\u{ad}ab", "\u{ad}cd"]);
+        assert_eq!((segments[0].brk, segments[0].width), (5, 2));
+        assert_eq!((segments[0].space_width, segments[0].hyphen), (1, None));
+    }
+
+    #[test]
+    fn test_balanced_breaks() {
+        let text = "The quick brown fox jumps over a lazy dog.";
+        assert_eq!(
+            lines(text, 30, greedy_breaks),
+            ["The quick brown fox jumps over", "a lazy dog."]
+        );
+        assert_eq!(
+            lines(text, 30, balanced_breaks),
+            ["The quick brown fox", "jumps over a lazy dog."]
+        );
+        assert_eq!(lines("a bb", 10, balanced_breaks), ["a bb"]);
+        assert_eq!(lines("", 10, balanced_breaks), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_pretty_breaks() {
+        let text = "The quick brown fox jumps over a lazy dog.";
+        let pretty = |segments: &[Segment], max_width| {
+            pretty_breaks(text, segments, max_width, &OrphanControl::default())
+        };
+        assert_eq!(
+            lines(text, 20, greedy_breaks),
+            ["The quick brown fox", "jumps over a lazy", "dog."]
+        );
+        assert_eq!(
+            lines(text, 20, pretty),
+            ["The quick brown", "fox jumps over a", "lazy dog."]
+        );
+        let pretty = |text| {
+            move |segments: &[Segment], max_width| {
+                pretty_breaks(text, segments, max_width, &OrphanControl::default())
+            }
+        };
+        assert_eq!(lines("one", 20, pretty("one")), ["one"]);
+        // the orphan cannot be fixed without overflowing
+        assert_eq!(lines("aaaa bbbb", 5, pretty("aaaa bbbb")), ["aaaa", "bbbb"]);
+
+        // `well-known`, which may break after its hyphen, is one word
+        let text = "The quick brown fox jumps over a lazy well-known";
+        assert_eq!(
+            lines(text, 20, greedy_breaks),
+            ["The quick brown fox", "jumps over a lazy", "well-known"]
+        );
+        assert_eq!(
+            lines(text, 20, pretty(text)),
+            ["The quick brown", "fox jumps over a", "lazy well-known"]
+        );
+        // every CJK character is a word
+        let text = "一二三四五六七八";
+        assert_eq!(lines(text, 14, pretty(text)), ["一二三四五六", "七八"]);
+    }
+
+    #[test]
+    fn test_hang_segments() {
+        let measure = |v: &str| v.chars().count();
+        let text = "(one, two. three)";
+        let segments = word_segments(0, Word::new(text, usize::MAX, 4, 0));
+        let hanging = HangingPunctuation {
+            first: true,
+            last: true,
+            allow_end: true,
+        };
+        let segments = hang_segments(text, segments, &hanging, measure);
+        let hangs: Vec<_> = segments
+            .iter()
+            .map(|v| (v.hang_start, v.hang_end))
+            .collect();
+        assert_eq!(hangs, [(1, 1), (0, 1), (0, 1)]);
+
+        // the parenthesis and the comma of `(one,` hang
+        assert_eq!(fit_width(&segments[..1]), 3);
+        assert_eq!(fit_width(&segments[..2]), 8);
+        assert_eq!(greedy_breaks(&segments, 8), [2, 3]);
+        assert_eq!(greedy_breaks(&segments, 7), [1, 2, 3]);
+        assert_eq!(optimal_breaks(&segments, 8), [2, 3]);
+
+        let segments = word_segments(0, Word::new(text, usize::MAX, 4, 0));
+        assert_eq!(greedy_breaks(&segments, 8), [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_white_space_segments() {
+        let measure = |v: &str| v.chars().count();
+        let text = "ab  cd";
+        let segments = word_segments(0, Word::new(text, usize::MAX, 4, 0));
+        let spans = |segments: Vec<Segment>| {
+            segments
+                .iter()
+                .map(|v| (v.start, v.end, v.brk, v.width, v.space_width))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            spans(break_spaces_segments(text, segments, measure)),
+            [(0, 3, 3, 3, 0), (3, 4, 4, 1, 0), (4, 6, 6, 2, 0)]
+        );
+
+        // a tab at the end and a segment of white space only hang after `ab`
+        let text = "ab\t \ncd";
+        let segments = vec![
+            Segment {
+                end: 3,
+                brk: 3,
+                width: 3,
+                ..Segment::new(0)
+            },
+            Segment {
+                end: 3,
+                brk: 5,
+                space_width: 2,
+                ..Segment::new(3)
+            },
+            Segment {
+                end: 7,
+                brk: 7,
+                width: 2,
+                ..Segment::new(5)
+            },
+        ];
+        let is_collapsible = |v: &str| matches!(v, " " | "\t" | "\n");
+        assert_eq!(
+            spans(collapse_segments(text, segments, is_collapsible, measure)),
+            [(0, 2, 5, 2, 3), (5, 7, 7, 2, 0)]
+        );
+    }
+
+    #[test]
+    fn test_autospace_segments() {
+        assert_eq!(autospace_positions("用Rust写的3种"), [3, 7, 13, 14]);
+        assert_eq!(autospace_positions("中文 abc，def"), []);
+
+        let text = "用Rust写 TextFlow库";
+        let segments = word_segments(0, Word::new(text, usize::MAX, 4, 0));
+        let segments = autospace_segments(text, segments, 1);
+        let widths: Vec<_> = segments
+            .iter()
+            .map(|v| (&text[v.start..v.end], v.width, v.space_width))
+            .collect();
+        // the gap before `库` hangs at a line end, the space needs no gap
+        assert_eq!(
+            widths,
+            [
+                ("用", 2, 1),
+                ("Rust", 4, 1),
+                ("写", 2, 1),
+                ("TextFlow", 8, 1),
+                ("库", 2, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_trim_segments() {
+        let measure = |v: &str| v.chars().count() * 2;
+        let text = "「「ab」。」（cd）";
+        let segments = word_segments(0, Word::new(text, usize::MAX, 4, 0));
+        let trim = |trim| {
+            trim_segments(text, segments.clone(), trim, measure)
+                .iter()
+                .map(|v| (v.width, v.trim_start, v.trim_end))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(trim(SpacingTrim::SpaceAll), [(12, 0, 0), (6, 0, 0)]);
+        // `「「` and `」。」` are trimmed inside the segment, `」` and `）` at a line end
+        assert_eq!(trim(SpacingTrim::Normal), [(9, 0, 1), (6, 0, 1)]);
+        assert_eq!(trim(SpacingTrim::TrimStart), [(9, 1, 1), (6, 1, 1)]);
+
+        let segments = trim_segments(text, segments.clone(), SpacingTrim::TrimStart, measure);
+        assert_eq!(line_width(&segments), 13);
+        assert_eq!(line_width(&segments[1..]), 4);
+        assert_eq!(greedy_breaks(&segments, 13), [2]);
+        assert_eq!(greedy_breaks(&segments, 12), [1, 2]);
+    }
+}
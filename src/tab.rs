@@ -0,0 +1,122 @@
+//! Tab stops: a tab advances to the next stop from the column it starts in, like `expand(1)`
+//! does, or lines up the text after it at an explicit stop.
+
+use crate::line::is_line_terminator;
+
+/// How the text after a tab lines up at its [`TabStop`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum TabAlign {
+    /// The text starts at the stop.
+    #[default]
+    Left,
+    /// The text ends at the stop, up to the next tab or the end of the line.
+    Right,
+    /// The first `.` of the text is at the stop, text without one ends there.
+    Decimal,
+}
+
+/// An explicit tab stop, `position` is a column from the start of the line.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TabStop {
+    pub position: usize,
+    pub align: TabAlign,
+}
+
+impl TabStop {
+    pub fn left(position: usize) -> TabStop {
+        TabStop {
+            position,
+            align: TabAlign::Left,
+        }
+    }
+
+    pub fn right(position: usize) -> TabStop {
+        TabStop {
+            position,
+            align: TabAlign::Right,
+        }
+    }
+
+    pub fn decimal(position: usize) -> TabStop {
+        TabStop {
+            position,
+            align: TabAlign::Decimal,
+        }
+    }
+}
+
+/// Explicit tab stops, past the last one a tab advances to the next multiple of `tab_width`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TabStops {
+    /// sorted by position
+    stops: Vec<TabStop>,
+}
+
+pub static NO_TAB_STOPS: TabStops = TabStops { stops: Vec::new() };
+
+impl TabStops {
+    pub fn new(stops: impl IntoIterator<Item = TabStop>) -> TabStops {
+        let mut stops: Vec<_> = stops.into_iter().collect();
+        stops.sort_by_key(|v| v.position);
+        TabStops { stops }
+    }
+
+    /// Left aligned stops at `positions`, e.g. `TabStops::at([8, 20, 32])`.
+    pub fn at(positions: impl IntoIterator<Item = usize>) -> TabStops {
+        TabStops::new(positions.into_iter().map(TabStop::left))
+    }
+
+    /// Width of a tab starting at `column`, followed by the text `after`, which is measured
+    /// with `measure` for right and decimal stops.
+    pub fn advance(
+        &self,
+        column: usize,
+        tab_width: usize,
+        after: &str,
+        measure: impl Fn(&str) -> usize,
+    ) -> usize {
+        let Some(stop) = self.stops.iter().find(|v| v.position > column) else {
+            return match tab_width {
+                0 => 0,
+                _ => tab_width - column % tab_width,
+            };
+        };
+
+        let run = after
+            .split(|ch| ch == '\t' || is_line_terminator(ch))
+            .next()
+            .unwrap_or_default();
+        let aligned = match stop.align {
+            TabAlign::Left => 0,
+            TabAlign::Right => measure(run),
+            TabAlign::Decimal => measure(run.split('.').next().unwrap_or_default()),
+        };
+        // text too wide for the stop starts right after the tab
+        stop.position.saturating_sub(column + aligned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_advance() {
+        let measure = |v: &str| v.chars().count();
+        let stops = TabStops::default();
+        assert_eq!(stops.advance(0, 8, "", measure), 8);
+        assert_eq!(stops.advance(3, 8, "", measure), 5);
+        assert_eq!(stops.advance(8, 8, "", measure), 8);
+        assert_eq!(stops.advance(3, 0, "", measure), 0);
+
+        let stops = TabStops::new([TabStop::right(20), TabStop::left(8), TabStop::decimal(32)]);
+        assert_eq!(stops.advance(3, 8, "abc", measure), 5);
+        // `abc` ends at 20, `12.5` has its `.` at 32
+        assert_eq!(stops.advance(10, 8, "abc\t12.5", measure), 7);
+        assert_eq!(stops.advance(20, 8, "12.5\tx", measure), 10);
+        assert_eq!(stops.advance(20, 8, "125", measure), 9);
+        assert_eq!(stops.advance(18, 8, "abcdef", measure), 0);
+        // past the last stop
+        assert_eq!(stops.advance(35, 8, "", measure), 5);
+    }
+}
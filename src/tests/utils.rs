@@ -27,3 +27,12 @@ macro_rules! assert_flow {
         }
     }};
 }
+
+#[macro_export]
+macro_rules! assert_lines {
+    ($text:expr, $flow:expr => $($expected:literal) +) => {{
+        let text = $text;
+        let lines = $flow.map(|line| line.slices(text)).collect::<Vec<_>>();
+        assert_eq!(lines, vec![$($expected),+]);
+    }};
+}
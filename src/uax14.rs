@@ -0,0 +1,613 @@
+//! Unicode Line Breaking Algorithm ([UAX #14](https://www.unicode.org/reports/tr14/)),
+//! default rules of Unicode 15.0, tailored only by [`LineBreakStrictness`].
+//!
+//! LB25 is the regular expression tailoring of numbers that the `LineBreakTest.txt` of the
+//! UCD is generated with, `(PR | PO) ? (OP | HY) ? NU (NU | SY | IS) * (CL | CP) ? (PR | PO) ?`,
+//! kept together as a whole. The conformance test runs against the ICU4X variant of that
+//! file, `tests/data/LineBreakTest.txt`, which is generated with the untailored pair rule
+//! instead; the cases where the two differ are listed, each with its pair, and skipped.
+
+use crate::dictionary::{self, Dictionary};
+use crate::line::LineBreakStrictness;
+use crate::word::{is_iteration_mark, is_loose_breakable};
+use unicode_linebreak::{break_property, BreakClass};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthChar;
+
+/// Line break classes, named as in UAX #14.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineBreakClass {
+    BK,
+    CR,
+    LF,
+    CM,
+    NL,
+    SG,
+    WJ,
+    ZW,
+    GL,
+    SP,
+    ZWJ,
+    B2,
+    BA,
+    BB,
+    HY,
+    CB,
+    CL,
+    CP,
+    EX,
+    IN,
+    NS,
+    OP,
+    QU,
+    IS,
+    NU,
+    PO,
+    PR,
+    SY,
+    AI,
+    AL,
+    CJ,
+    EB,
+    EM,
+    H2,
+    H3,
+    HL,
+    ID,
+    JL,
+    JV,
+    JT,
+    RI,
+    SA,
+    XX,
+}
+
+use LineBreakClass::*;
+
+impl From<char> for LineBreakClass {
+    fn from(ch: char) -> Self {
+        match break_property(ch as u32) {
+            BreakClass::Mandatory => BK,
+            BreakClass::CarriageReturn => CR,
+            BreakClass::LineFeed => LF,
+            BreakClass::CombiningMark => CM,
+            BreakClass::NextLine => NL,
+            BreakClass::Surrogate => SG,
+            BreakClass::WordJoiner => WJ,
+            BreakClass::ZeroWidthSpace => ZW,
+            BreakClass::NonBreakingGlue => GL,
+            BreakClass::Space => SP,
+            BreakClass::ZeroWidthJoiner => ZWJ,
+            BreakClass::BeforeAndAfter => B2,
+            BreakClass::After => BA,
+            BreakClass::Before => BB,
+            BreakClass::Hyphen => HY,
+            BreakClass::Contingent => CB,
+            BreakClass::ClosePunctuation => CL,
+            BreakClass::CloseParenthesis => CP,
+            BreakClass::Exclamation => EX,
+            BreakClass::Inseparable => IN,
+            BreakClass::NonStarter => NS,
+            BreakClass::OpenPunctuation => OP,
+            BreakClass::Quotation => QU,
+            BreakClass::InfixSeparator => IS,
+            BreakClass::Numeric => NU,
+            BreakClass::Postfix => PO,
+            BreakClass::Prefix => PR,
+            BreakClass::Symbol => SY,
+            BreakClass::Ambiguous => AI,
+            BreakClass::Alphabetic => AL,
+            BreakClass::ConditionalJapaneseStarter => CJ,
+            BreakClass::EmojiBase => EB,
+            BreakClass::EmojiModifier => EM,
+            BreakClass::HangulLvSyllable => H2,
+            BreakClass::HangulLvtSyllable => H3,
+            BreakClass::HebrewLetter => HL,
+            BreakClass::Ideographic => ID,
+            BreakClass::HangulLJamo => JL,
+            BreakClass::HangulVJamo => JV,
+            BreakClass::HangulTJamo => JT,
+            BreakClass::RegionalIndicator => RI,
+            BreakClass::ComplexContext => SA,
+            BreakClass::Unknown => XX,
+        }
+    }
+}
+
+/// LB1: resolve classes whose behaviour the default algorithm leaves open.
+///
+/// `Strict` is the untailored algorithm, `Normal` lets small kana start a line (CJ as ID)
+/// and `Loose` also iteration marks, CJK hyphens and centered punctuation.
+fn resolve_class(ch: char, strictness: LineBreakStrictness) -> LineBreakClass {
+    let loose = strictness == LineBreakStrictness::Loose;
+    match LineBreakClass::from(ch) {
+        AI | SG | XX => AL,
+        // combining marks of SA scripts have no advance of their own
+        SA if ch.width() == Some(0) => CM,
+        SA => AL,
+        CJ if strictness == LineBreakStrictness::Strict => NS,
+        CJ => ID,
+        _ if loose && (is_iteration_mark(ch) || is_loose_breakable(ch)) => ID,
+        class => class,
+    }
+}
+
+/// East Asian Width is F, W or H, which exempts OP/CP from LB30.
+fn is_east_asian_wide(ch: char) -> bool {
+    ch.width() == Some(2) || matches!(ch as u32, 0xFF61..=0xFFDC | 0xFFE8..=0xFFEE)
+}
+
+/// Unassigned code points that are reserved for future pictographs, the
+/// `[\p{Extended_Pictographic}&\p{Cn}]` of LB30b: the `<reserved>` ranges of
+/// `emoji-data.txt` in Unicode 15.0.
+fn is_reserved_pictographic(ch: char) -> bool {
+    matches!(
+        ch as u32,
+        0x1F02C..=0x1F02F
+            | 0x1F094..=0x1F09F
+            | 0x1F0AF..=0x1F0B0
+            | 0x1F0C0
+            | 0x1F0D0
+            | 0x1F0F6..=0x1F0FF
+            | 0x1F1AE..=0x1F1E5
+            | 0x1F203..=0x1F20F
+            | 0x1F23C..=0x1F23F
+            | 0x1F249..=0x1F24F
+            | 0x1F252..=0x1F25F
+            | 0x1F266..=0x1F2FF
+            | 0x1F6D8..=0x1F6DB
+            | 0x1F6ED..=0x1F6EF
+            | 0x1F6FD..=0x1F6FF
+            | 0x1F777..=0x1F77A
+            | 0x1F7DA..=0x1F7DF
+            | 0x1F7EC..=0x1F7EF
+            | 0x1F7F1..=0x1F7FF
+            | 0x1F80C..=0x1F80F
+            | 0x1F848..=0x1F84F
+            | 0x1F85A..=0x1F85F
+            | 0x1F888..=0x1F88F
+            | 0x1F8AE..=0x1F8AF
+            | 0x1F8B2..=0x1F8FF
+            | 0x1FA54..=0x1FA5F
+            | 0x1FA6E..=0x1FA6F
+            | 0x1FA7D..=0x1FA7F
+            | 0x1FA89..=0x1FA8F
+            | 0x1FABE
+            | 0x1FAC6..=0x1FACD
+            | 0x1FADC..=0x1FADF
+            | 0x1FAE9..=0x1FAEF
+            | 0x1FAF9..=0x1FAFF
+            | 0x1FC00..=0x1FFFD
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BreakOpportunity {
+    /// The line must break here (after BK, CR, LF, NL and at the end of text).
+    Mandatory,
+    /// The line may break here.
+    Allowed,
+}
+
+/// A segment that is too long may be cut between the graphemes `prev` and `next`: the pair
+/// rules that keep punctuation, glue and joiners with their neighbours still hold, those
+/// that keep letters and digits together do not.
+pub fn can_cut_between(prev: &str, next: &str, strictness: LineBreakStrictness) -> bool {
+    let class = |v: &str| v.chars().next().map(|ch| resolve_class(ch, strictness));
+    let (Some(prev), Some(next)) = (class(prev), class(next)) else {
+        return false;
+    };
+    !matches!(
+        next,
+        BA | HY | CL | CP | EX | IS | NS | GL | WJ | ZW | SP | CM | ZWJ
+    ) && !matches!(prev, OP | BB | GL | WJ)
+}
+
+/// All break opportunities of `text` as `(byte offset, kind)`, a break at offset `i` is
+/// between the characters before and after `i`. The end of text is always a mandatory break.
+///
+/// With `keep_all`, letters of CJK text (ID and Hangul classes) do not break between each
+/// other, as in CSS `word-break: keep-all`. Runs of SA characters break between the words
+/// found by `dictionary`, or by the embedded dictionary of their script.
+pub fn line_breaks(
+    text: &str,
+    strictness: LineBreakStrictness,
+    keep_all: bool,
+    dictionary: Option<&Dictionary>,
+) -> Vec<(usize, BreakOpportunity)> {
+    let breaks = rule_breaks(text, strictness, keep_all);
+    if strictness != LineBreakStrictness::Anywhere {
+        return with_dictionary_breaks(text, breaks, dictionary);
+    }
+
+    // every grapheme boundary, except before spaces and line terminators (LB6, LB7)
+    let mut mandatory = breaks
+        .into_iter()
+        .filter(|v| v.1 == BreakOpportunity::Mandatory)
+        .map(|v| v.0)
+        .peekable();
+    let mut breaks = vec![];
+    for (pos, grapheme) in text.grapheme_indices(true).skip(1) {
+        if mandatory.next_if(|&v| v == pos).is_some() {
+            breaks.push((pos, BreakOpportunity::Mandatory));
+        } else if grapheme
+            .chars()
+            .next()
+            .is_some_and(|ch| !matches!(LineBreakClass::from(ch), BK | CR | LF | NL | SP | ZW))
+        {
+            breaks.push((pos, BreakOpportunity::Allowed));
+        }
+    }
+    breaks.extend(mandatory.map(|v| (v, BreakOpportunity::Mandatory)));
+    breaks
+}
+
+/// Add the word boundaries inside runs of SA characters, which the rules leave unbroken.
+fn with_dictionary_breaks(
+    text: &str,
+    breaks: Vec<(usize, BreakOpportunity)>,
+    dictionary: Option<&Dictionary>,
+) -> Vec<(usize, BreakOpportunity)> {
+    let words = dictionary::dictionary_breaks(text, dictionary);
+    if words.is_empty() {
+        return breaks;
+    }
+
+    let mut merged: Vec<(usize, BreakOpportunity)> = breaks
+        .into_iter()
+        .chain(words.into_iter().map(|v| (v, BreakOpportunity::Allowed)))
+        .collect();
+    merged.sort_by_key(|v| v.0);
+    merged.dedup_by_key(|v| v.0);
+    merged
+}
+
+/// Break opportunities of the rules LB4 - LB31.
+fn rule_breaks(
+    text: &str,
+    strictness: LineBreakStrictness,
+    keep_all: bool,
+) -> Vec<(usize, BreakOpportunity)> {
+    let mut breaks = Vec::new();
+    let mut chars = text.char_indices();
+
+    let Some((_, first)) = chars.next() else {
+        return breaks;
+    };
+
+    // effective class / character before the current position, after LB9 and LB10
+    let mut prev = match resolve_class(first, strictness) {
+        CM | ZWJ => AL,
+        class => class,
+    };
+    let mut prev_char = first;
+    // class before `prev`, for LB21a
+    let mut prev_prev = prev;
+    // class before a run of spaces, for LB8 and LB14 - LB17
+    let mut before_spaces = prev;
+    // the previous character itself was a ZWJ, for LB8a
+    let mut after_zwj = resolve_class(first, strictness) == ZWJ;
+    // number of consecutive regional indicators, for LB30a
+    let mut ri_count = (prev == RI) as usize;
+    // `prev` ends `NU (NU | SY | IS)*`, or that followed by a CL or CP, for LB25
+    let mut in_number = prev == NU;
+    let mut number_closed = false;
+
+    for (pos, ch) in chars {
+        let raw = resolve_class(ch, strictness);
+        // LB9: a combining sequence takes the class of its base, LB10: otherwise it is AL
+        let absorbed = matches!(raw, CM | ZWJ) && !matches!(prev, BK | CR | LF | NL | SP | ZW);
+        let class = match raw {
+            CM | ZWJ => AL,
+            class => class,
+        };
+
+        let opportunity = 'rule: {
+            // LB4, LB5
+            match prev {
+                BK | LF | NL => break 'rule Some(BreakOpportunity::Mandatory),
+                CR if class != LF => break 'rule Some(BreakOpportunity::Mandatory),
+                CR => break 'rule None,
+                _ => {}
+            }
+            // LB6, LB7
+            if matches!(class, BK | CR | LF | NL | SP | ZW) {
+                break 'rule None;
+            }
+            // LB8
+            if before_spaces == ZW {
+                break 'rule Some(BreakOpportunity::Allowed);
+            }
+            // LB8a
+            if after_zwj {
+                break 'rule None;
+            }
+            // LB9
+            if absorbed {
+                break 'rule None;
+            }
+            // LB11, LB12, LB12a
+            if class == WJ || prev == WJ || prev == GL {
+                break 'rule None;
+            }
+            if class == GL && !matches!(prev, SP | BA | HY) {
+                break 'rule None;
+            }
+            // LB13
+            if matches!(class, CL | CP | EX | IS | SY) {
+                break 'rule None;
+            }
+            // LB14 - LB17
+            match (before_spaces, class) {
+                (OP, _) | (QU, OP) | (CL | CP, NS) | (B2, B2) => break 'rule None,
+                _ => {}
+            }
+            // LB18
+            if prev == SP {
+                break 'rule Some(BreakOpportunity::Allowed);
+            }
+            // LB19, LB20
+            if class == QU || prev == QU {
+                break 'rule None;
+            }
+            if class == CB || prev == CB {
+                break 'rule Some(BreakOpportunity::Allowed);
+            }
+            // LB21, LB21a, LB21b, LB22
+            if matches!(class, BA | HY | NS | IN) || prev == BB {
+                break 'rule None;
+            }
+            if prev_prev == HL && matches!(prev, HY | BA) {
+                break 'rule None;
+            }
+            if prev == SY && class == HL {
+                break 'rule None;
+            }
+            let no_break = match (prev, class) {
+                // LB23, LB23a, LB24
+                (AL | HL, NU) | (NU, AL | HL) => true,
+                (PR, ID | EB | EM) | (ID | EB | EM, PO) => true,
+                (PR | PO, AL | HL) | (AL | HL, PR | PO) => true,
+                // LB25, tailored as its regular expression
+                (PR | PO, NU) | (OP | HY, NU) => true,
+                (PR | PO, OP | HY) => {
+                    let mut next = text[pos + ch.len_utf8()..]
+                        .chars()
+                        .map(|v| resolve_class(v, strictness));
+                    next.find(|v| !matches!(v, CM | ZWJ)) == Some(NU)
+                }
+                (_, NU | SY | IS | CL | CP) if in_number => true,
+                (_, PO | PR) if in_number || number_closed => true,
+                // LB26, LB27
+                (JL, JL | JV | H2 | H3) | (JV | H2, JV | JT) | (JT | H3, JT) => true,
+                (JL | JV | JT | H2 | H3, PO) | (PR, JL | JV | JT | H2 | H3) => true,
+                // LB28, LB29
+                (AL | HL, AL | HL) | (IS, AL | HL) => true,
+                // LB30
+                (AL | HL | NU, OP) => !is_east_asian_wide(ch),
+                (CP, AL | HL | NU) => !is_east_asian_wide(prev_char),
+                // LB30a
+                (RI, RI) => ri_count % 2 == 1,
+                // LB30b
+                (EB, EM) => true,
+                (ID, EM) => is_reserved_pictographic(prev_char),
+                _ => false,
+            };
+            if no_break {
+                break 'rule None;
+            }
+            // keep-all tailoring of LB31
+            let is_letter = |class| matches!(class, AL | HL | NU | ID | H2 | H3 | JL | JV | JT);
+            if keep_all && is_letter(prev) && is_letter(class) {
+                break 'rule None;
+            }
+            // LB31
+            Some(BreakOpportunity::Allowed)
+        };
+
+        if let Some(opportunity) = opportunity {
+            breaks.push((pos, opportunity));
+        }
+
+        after_zwj = raw == ZWJ;
+        if absorbed {
+            continue;
+        }
+
+        ri_count = if class == RI { ri_count + 1 } else { 0 };
+        (in_number, number_closed) = match class {
+            NU => (true, false),
+            SY | IS if in_number => (true, false),
+            CL | CP if in_number => (false, true),
+            _ => (false, false),
+        };
+        prev_prev = prev;
+        prev = class;
+        prev_char = ch;
+        if class != SP {
+            before_spaces = class;
+        }
+    }
+
+    breaks.push((text.len(), BreakOpportunity::Mandatory));
+    breaks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_breaks() {
+        assert_eq!(
+            line_breaks("a b \nc", LineBreakStrictness::Normal, false, None),
+            vec![
+                (2, BreakOpportunity::Allowed),
+                (5, BreakOpportunity::Mandatory),
+                (6, BreakOpportunity::Mandatory),
+            ]
+        );
+        assert_eq!(
+            line_breaks("", LineBreakStrictness::Normal, false, None),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_strictness() {
+        let positions = |text: &str, strictness| -> Vec<usize> {
+            line_breaks(text, strictness, false, None)
+                .into_iter()
+                .map(|v| v.0)
+                .collect()
+        };
+
+        // ョ is CJ, 々 is NS
+        let text = "ジョ人々";
+        assert_eq!(positions(text, LineBreakStrictness::Strict), [6, 12]);
+        assert_eq!(positions(text, LineBreakStrictness::Normal), [3, 6, 12]);
+        assert_eq!(positions(text, LineBreakStrictness::Loose), [3, 6, 9, 12]);
+
+        let text = "a, b\nc";
+        assert_eq!(positions(text, LineBreakStrictness::Normal), [3, 5, 6]);
+        assert_eq!(positions(text, LineBreakStrictness::Anywhere), [1, 3, 5, 6]);
+    }
+
+    #[test]
+    fn test_keep_all() {
+        let positions = |text: &str, keep_all| -> Vec<usize> {
+            line_breaks(text, LineBreakStrictness::Normal, keep_all, None)
+                .into_iter()
+                .map(|v| v.0)
+                .collect()
+        };
+
+        let text = "한국어 텍스트";
+        assert_eq!(positions(text, false), [3, 6, 10, 13, 16, 19]);
+        assert_eq!(positions(text, true), [10, 19]);
+        assert_eq!(positions("你好，世界", true), [9, 15]);
+    }
+
+    #[test]
+    fn test_numbers() {
+        let positions = |text: &str| -> Vec<usize> {
+            line_breaks(text, LineBreakStrictness::Strict, false, None)
+                .into_iter()
+                .map(|v| v.0)
+                .collect()
+        };
+
+        // a whole number stays together, prefix, brackets and suffix included
+        assert_eq!(positions("$(1.5)%"), [7]);
+        assert_eq!(positions("-1,000"), [6]);
+        // punctuation outside a number does not join it
+        assert_eq!(positions("$(a"), [1, 3]);
+        assert_eq!(positions("a.2"), [2, 3]);
+        assert_eq!(positions("x)%"), [2, 3]);
+    }
+
+    #[test]
+    fn test_reserved_pictographic() {
+        let breaks = |text: &str| line_breaks(text, LineBreakStrictness::Strict, false, None);
+        // an unassigned pictograph keeps its emoji modifier, an assigned one without the
+        // Emoji_Modifier_Base property does not
+        assert_eq!(breaks("\u{1F02C}\u{1F3FB}").len(), 1);
+        assert_eq!(breaks("\u{1FAF9}\u{1F3FB}").len(), 1);
+        assert_eq!(breaks("\u{1F000}\u{1F3FB}").len(), 2);
+        assert_eq!(breaks("\u{1F0C1}\u{1F3FB}").len(), 2);
+        assert!(!is_reserved_pictographic('\u{1F600}'));
+        assert!(is_reserved_pictographic('\u{1FFFD}'));
+    }
+
+    /// Cases of the ICU4X data that the untailored LB25 of its generator keeps together, and
+    /// that the regular expression of LB25 breaks, each with the pair the data joins.
+    const LB25_EXCLUDED: &[(&str, &str)] = &[
+        ("× 007D × 0025 ÷", "`CL × PO` outside a number"),
+        ("× 007D × 0308 × 0025 ÷", "`CL × PO` outside a number"),
+        ("× 007D × 0024 ÷", "`CL × PR` outside a number"),
+        ("× 007D × 0308 × 0024 ÷", "`CL × PR` outside a number"),
+        ("× 002C × 0030 ÷", "`IS × NU` outside a number"),
+        ("× 002C × 0308 × 0030 ÷", "`IS × NU` outside a number"),
+        ("× 0025 × 2329 ÷", "`PO × OP` without a following `NU`"),
+        ("× 0025 × 0308 × 2329 ÷", "`PO × OP` without a following `NU`"),
+        ("× 0025 × 0028 ÷", "`PO × OP` without a following `NU`"),
+        ("× 0025 × 0308 × 0028 ÷", "`PO × OP` without a following `NU`"),
+        ("× 0024 × 2329 ÷", "`PR × OP` without a following `NU`"),
+        ("× 0024 × 0308 × 2329 ÷", "`PR × OP` without a following `NU`"),
+        ("× 0024 × 0028 ÷", "`PR × OP` without a following `NU`"),
+        ("× 0024 × 0308 × 0028 ÷", "`PR × OP` without a following `NU`"),
+        ("× 002F × 0030 ÷", "`SY × NU` outside a number"),
+        ("× 002F × 0308 × 0030 ÷", "`SY × NU` outside a number"),
+        ("× 0029 × 0025 ÷", "`CP × PO` outside a number"),
+        ("× 0029 × 0308 × 0025 ÷", "`CP × PO` outside a number"),
+        ("× 0029 × 0024 ÷", "`CP × PR` outside a number"),
+        ("× 0029 × 0308 × 0024 ÷", "`CP × PR` outside a number"),
+        ("× 0065 × 0071 × 0075 × 0061 × 006C × 0073 × 0020 × 002E × 0033 × 0035 × 0020 ÷ 0063 × 0065 × 006E × 0074 × 0073 ÷", "`IS × NU` outside a number"),
+        ("× 0063 × 006F × 0064 × 0065 × 005C × 0028 × 0073 × 005C × 0029 ÷", "`PR × OP` without a following `NU`"),
+        ("× 0063 × 006F × 0064 × 0065 × 005C × 007B × 0073 × 005C × 007D ÷", "`PR × OP` without a following `NU`"),
+        ("× 0061 × 002E × 0032 × 0020 ÷", "`IS × NU` outside a number"),
+        ("× 0061 × 002E × 0032 × 0020 ÷ 0915 ÷", "`IS × NU` outside a number"),
+        ("× 0061 × 002E × 0032 × 0020 ÷ 672C ÷", "`IS × NU` outside a number"),
+        ("× 0061 × 002E × 0032 × 3000 ÷ 672C ÷", "`IS × NU` outside a number"),
+        ("× 0061 × 002E × 0032 × 3000 ÷ 307E ÷", "`IS × NU` outside a number"),
+        ("× 0061 × 002E × 0032 × 3000 ÷ 0033 ÷", "`IS × NU` outside a number"),
+        ("× 0041 × 002E × 0031 × 0020 ÷ BABB ÷", "`IS × NU` outside a number"),
+        ("× BD24 ÷ C5B4 × 002E × 0020 ÷ 0041 × 002E × 0032 × 0020 ÷ BCFC ÷", "`IS × NU` outside a number"),
+        ("× BD10 ÷ C694 × 002E × 0020 ÷ 0041 × 002E × 0033 × 0020 ÷ BABB ÷", "`IS × NU` outside a number"),
+        ("× C694 × 002E × 0020 ÷ 0041 × 002E × 0034 × 0020 ÷ BABB ÷", "`IS × NU` outside a number"),
+        ("× 0061 × 002E × 0032 × 3000 ÷ 300C ÷", "`IS × NU` outside a number"),
+    ];
+
+    /// Conformance against the ICU4X variant of `LineBreakTest.txt` (Unicode 15.0, untailored
+    /// LB25), less the cases of [`LB25_EXCLUDED`], see the module documentation.
+    #[test]
+    fn test_line_break_test_txt() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/LineBreakTest.txt");
+        let data = std::fs::read_to_string(path).unwrap();
+
+        let mut cases = 0;
+        let mut excluded = vec![];
+        let mut failures = vec![];
+        for line in data.lines() {
+            let case = line.split('#').next().unwrap().trim();
+            if case.is_empty() {
+                continue;
+            }
+
+            let mut text = String::new();
+            let mut expected = vec![];
+            for token in case.split_whitespace() {
+                match token {
+                    "÷" if !text.is_empty() => expected.push(text.len()),
+                    "÷" | "×" => {}
+                    hex => {
+                        text.push(char::from_u32(u32::from_str_radix(hex, 16).unwrap()).unwrap())
+                    }
+                }
+            }
+
+            cases += 1;
+            let actual: Vec<usize> = line_breaks(&text, LineBreakStrictness::Strict, false, None)
+                .into_iter()
+                .map(|v| v.0)
+                .collect();
+            if LB25_EXCLUDED.iter().any(|v| v.0 == case) {
+                // an excluded case must still differ, or it belongs back in the test
+                assert_ne!(actual, expected, "{case}");
+                excluded.push(case);
+            } else if actual != expected {
+                failures.push(line);
+            }
+        }
+
+        assert!(cases > 7000);
+        assert_eq!(excluded.len(), LB25_EXCLUDED.len());
+        assert!(
+            failures.is_empty(),
+            "{} failures:\n{}",
+            failures.len(),
+            failures.join("\n")
+        );
+    }
+}
@@ -1,13 +1,22 @@
+use crate::dictionary::{self, Dictionary};
+use crate::line::{LineBreakStrictness, WhiteSpace};
+use crate::link::{TextLinks, NO_LINKS};
+use crate::measure::{AmbiguousWidth, MonospaceMeasurer, WidthMeasurer};
+use crate::tab::{TabStops, NO_TAB_STOPS};
+use crate::uax14::LineBreakClass;
 use std::iter::Peekable;
-use std::ops::Not;
-use std::str::CharIndices;
+use unicode_segmentation::{GraphemeIndices, UnicodeSegmentation};
+use unicode_width::UnicodeWidthStr;
 
-#[allow(non_camel_case_types)]
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
 #[derive(PartialEq, Debug, Clone)]
 pub enum WordType {
     LATIN,
     CJK,
+    EMOJI,
     HYPHEN,
+    /// U+00AD, an invisible break opportunity inside a word
+    SOFT_HYPHEN,
     NUMBER,
     OPEN_PUNCTUATION,
     CLOSE_PUNCTUATION,
@@ -16,6 +25,8 @@ pub enum WordType {
     SPACE,
     TAB,
     QUOTATION,
+    /// a piece of a URL, e-mail address or file path, between two of its break opportunities
+    LINK,
     UNKNOWN,
 }
 
@@ -34,8 +45,26 @@ pub struct WordInfo {
     pub ideal_width: usize,
 }
 
-pub struct Word<'a> {
-    char_indices: Peekable<CharIndices<'a>>,
+static DEFAULT_MEASURER: MonospaceMeasurer = MonospaceMeasurer::new(AmbiguousWidth::Narrow);
+
+pub struct Word<'a, M: WidthMeasurer = MonospaceMeasurer> {
+    text: &'a str,
+    graphemes: Peekable<GraphemeIndices<'a>>,
+    measurer: &'a M,
+    strictness: LineBreakStrictness,
+    keep_all: bool,
+    break_all: bool,
+    dictionary: Option<&'a Dictionary>,
+    /// end of the last run of Thai, Lao, Khmer or Myanmar text, and the word boundaries in it
+    dictionary_run_end: usize,
+    dictionary_breaks: Vec<usize>,
+    /// links of the whole text, which starts `offset` bytes before the text of the words
+    links: &'a TextLinks,
+    offset: usize,
+    tab_stops: &'a TabStops,
+    white_space: WhiteSpace,
+    /// width of the words so far, the column where the next one starts
+    column: usize,
 
     word_info_prev: Option<WordInfo>,
 
@@ -44,15 +73,124 @@ pub struct Word<'a> {
     letter_space: isize,
 }
 
-fn is_latin(ch: char) -> bool {
-    ch >= 'a' && ch <= 'z' || ch >= 'A' && ch <= 'Z'
+const ZWJ: char = '\u{200d}';
+
+/// Characters that forbid a break on both sides of them: no-break spaces (U+00A0, U+2007,
+/// U+202F), the non-breaking hyphen and word joiners (U+2060, U+FEFF), class GL and WJ of
+/// UAX #14.
+pub fn is_glue(ch: char) -> bool {
+    matches!(
+        ch,
+        '\u{a0}' | '\u{2007}' | '\u{2011}' | '\u{202f}' | '\u{2060}' | '\u{feff}'
+    )
+}
+
+/// Letters of alphabetic scripts (Latin, Cyrillic, Greek, Armenian, Georgian, ...), which
+/// form words that only break at spaces and punctuation.
+///
+/// Combining marks need no check of their own, they are part of the grapheme cluster of
+/// their base letter.
+fn is_alphabetic(ch: char) -> bool {
+    ch.is_alphabetic() && !is_cjk(ch)
+}
+
+/// Ideographs, Kana, Hangul, Bopomofo and the CJK symbol blocks, which break like ideographs.
+///
+/// CJK punctuation in these blocks is classified before it gets here.
+pub fn is_cjk(ch: char) -> bool {
+    matches!(
+        ch as u32,
+        0x1100..=0x11FF // hangul jamo
+            | 0x2E80..=0x2FDF // cjk radicals supplement, kangxi radicals
+            | 0x2FF0..=0x2FFF // ideographic description characters
+            | 0x3000..=0x303F // cjk symbols and punctuation
+            | 0x3040..=0x30FF // hiragana, katakana
+            | 0x3100..=0x312F // bopomofo
+            | 0x3130..=0x318F // hangul compatibility jamo
+            | 0x3190..=0x31FF // kanbun, bopomofo extended, cjk strokes, katakana phonetic extensions
+            | 0x3200..=0x33FF // enclosed cjk letters and months, cjk compatibility
+            | 0x3400..=0x4DBF // cjk unified ideographs extension a
+            | 0x4E00..=0x9FFF // cjk unified ideographs
+            | 0xA960..=0xA97F // hangul jamo extended-a
+            | 0xAC00..=0xD7FF // hangul syllables, hangul jamo extended-b
+            | 0xF900..=0xFAFF // cjk compatibility ideographs
+            | 0xFF65..=0xFFDC // halfwidth katakana and hangul
+            | 0x1AFF0..=0x1B16F // kana extended-b, kana supplement, kana extended-a, small kana extension
+            | 0x20000..=0x2FA1F // cjk unified ideographs extension b - f, compatibility supplement
+            | 0x30000..=0x323AF // cjk unified ideographs extension g, h
+    )
 }
 
-fn is_cjk(ch: char) -> bool {
-    ch >= '\u{4e00}' && ch <= '\u{9fff}'
+fn is_pictographic(ch: char) -> bool {
+    matches!(
+        ch as u32,
+        0x00A9 | 0x00AE | 0x203C | 0x2049 | 0x2122 | 0x2139
+            | 0x2194..=0x2199
+            | 0x21A9..=0x21AA
+            | 0x231A..=0x231B
+            | 0x2328 | 0x2388 | 0x23CF
+            | 0x23E9..=0x23F3
+            | 0x23F8..=0x23FA
+            | 0x24C2
+            | 0x25AA..=0x25AB
+            | 0x25B6 | 0x25C0
+            | 0x25FB..=0x25FE
+            | 0x2600..=0x27BF // misc symbols, dingbats
+            | 0x2934..=0x2935
+            | 0x2B05..=0x2B07
+            | 0x2B1B..=0x2B1C
+            | 0x2B50 | 0x2B55
+            | 0x1F000..=0x1FAFF // mahjong .. symbols and pictographs extended-a
+    )
 }
 
-fn is_open_punctuation(ch: char) -> bool {
+/// A grapheme cluster displayed as an emoji (UAX #14 classes ID/EB/EM, RI pairs).
+///
+/// Emoji and text presentation are told apart by width: a pictograph followed by VS15, or
+/// one without `Emoji_Presentation` and no VS16, is a narrow text symbol.
+fn is_emoji(grapheme: &str) -> bool {
+    let Some(base) = grapheme.chars().next() else {
+        return false;
+    };
+
+    // keycap sequence: [0-9#*] VS16? U+20E3
+    if grapheme.ends_with('\u{20e3}') {
+        return true;
+    }
+
+    is_pictographic(base) && grapheme.width() == 2
+}
+
+/// Small kana and the prolonged sound mark, which `Strict` keeps from starting a line.
+pub fn is_small_kana(ch: char) -> bool {
+    matches!(
+        ch,
+        'ぁ' | 'ぃ' | 'ぅ' | 'ぇ' | 'ぉ' | 'っ' | 'ゃ' | 'ゅ' | 'ょ' | 'ゎ' | 'ゕ' | 'ゖ'
+            | 'ァ' | 'ィ' | 'ゥ' | 'ェ' | 'ォ' | 'ッ' | 'ャ' | 'ュ' | 'ョ' | 'ヮ' | 'ヵ' | 'ヶ'
+            | '\u{31f0}'..='\u{31ff}' // katakana phonetic extensions
+            | '\u{ff67}'..='\u{ff70}' // halfwidth small katakana, ｰ
+            | 'ー'
+    )
+}
+
+/// Iteration marks, which only `Loose` lets start a line.
+pub fn is_iteration_mark(ch: char) -> bool {
+    matches!(ch, '々' | '〻' | 'ゝ' | 'ゞ' | 'ヽ' | 'ヾ')
+}
+
+/// CJK hyphens and centered punctuation that `Loose` lets start a line.
+///
+/// The Latin hyphens `‐` and `–` are not among them: CSS only lets a loose line break
+/// before them after a CJK character, and they keep their class in Latin text.
+pub fn is_loose_breakable(ch: char) -> bool {
+    matches!(
+        ch,
+        '〜' | '゠' // hyphens
+            | '・' | '：' | '；' | '･' | '‼' | '⁇' | '⁈' | '⁉' | '！' | '？' // centered punctuation
+    )
+}
+
+pub fn is_open_punctuation(ch: char) -> bool {
     [
         '(', '[', '{', '<', '（', '「', '『', '【', '〔', '〈', '《', '⦗', '⟨', '‘', '“',
     ]
@@ -68,7 +206,7 @@ fn is_close_punctuation(ch: char) -> bool {
     .contains(&ch)
 }
 
-fn is_quotation(ch: char) -> bool {
+pub fn is_quotation(ch: char) -> bool {
     [
         0x22,   // '"'
         0x27,   // '''
@@ -86,55 +224,169 @@ fn is_quotation(ch: char) -> bool {
     .contains(&(ch as u32))
 }
 
+impl From<&str> for WordType {
+    /// Classify a grapheme cluster by its base character.
+    fn from(grapheme: &str) -> Self {
+        if is_emoji(grapheme) {
+            return WordType::EMOJI;
+        }
+        grapheme
+            .chars()
+            .next()
+            .map_or(WordType::UNKNOWN, WordType::from)
+    }
+}
+
+impl WordType {
+    /// Classify a grapheme cluster under the kinsoku rules of `strictness`.
+    pub fn classify(grapheme: &str, strictness: LineBreakStrictness) -> WordType {
+        let word_type = WordType::from(grapheme);
+        let Some(ch) = grapheme.chars().next() else {
+            return word_type;
+        };
+
+        match strictness {
+            LineBreakStrictness::Anywhere => match word_type {
+                WordType::NEWLINE | WordType::RETURN | WordType::SPACE | WordType::TAB => word_type,
+                _ => WordType::CJK,
+            },
+            LineBreakStrictness::Strict if is_small_kana(ch) => WordType::CLOSE_PUNCTUATION,
+            LineBreakStrictness::Strict | LineBreakStrictness::Normal
+                if is_iteration_mark(ch) || is_loose_breakable(ch) =>
+            {
+                WordType::CLOSE_PUNCTUATION
+            }
+            LineBreakStrictness::Loose if is_iteration_mark(ch) || is_loose_breakable(ch) => {
+                WordType::CJK
+            }
+            _ => word_type,
+        }
+    }
+}
+
 impl From<char> for WordType {
     fn from(ch: char) -> Self {
         match ch {
-            ch if is_latin(ch) => WordType::LATIN,
-            ch if is_cjk(ch) => WordType::CJK,
-            '-' => WordType::HYPHEN,
-            ch if ch >= '0' && ch <= '9' => WordType::NUMBER,
+            ch if is_alphabetic(ch) => WordType::LATIN,
+            ch if is_emoji(ch.encode_utf8(&mut [0; 4])) => WordType::EMOJI,
+            // a line breaks after these hyphens and not before them, as `BA` of UAX #14
+            '-' | '‐' | '–' => WordType::HYPHEN,
+            '\u{ad}' => WordType::SOFT_HYPHEN,
+            ch if ch.is_ascii_digit() => WordType::NUMBER,
             ch if is_open_punctuation(ch) => WordType::OPEN_PUNCTUATION,
             ch if is_close_punctuation(ch) => WordType::CLOSE_PUNCTUATION,
-            '\n' => WordType::NEWLINE,
+            // the mandatory breaks of UAX #14 other than `\r`
+            '\n' | '\u{b}' | '\u{c}' | '\u{85}' | '\u{2028}' | '\u{2029}' => WordType::NEWLINE,
             '\r' => WordType::RETURN,
-            ' ' => WordType::SPACE,
+            // a zero width space is an invisible break opportunity
+            ' ' | '\u{200b}' => WordType::SPACE,
             '\t' => WordType::TAB,
             ch if is_quotation(ch) => WordType::QUOTATION,
+            ch if is_cjk(ch) => WordType::CJK,
             _ => WordType::UNKNOWN,
         }
     }
 }
 
-fn get_char_width(ch: char, tab_width: usize) -> usize {
-    let char_type = WordType::from(ch);
-    match char_type {
-        WordType::LATIN => 1,
-        WordType::CJK => 2,
-        WordType::HYPHEN => 1,
-        WordType::NUMBER => 1,
-        WordType::CLOSE_PUNCTUATION | WordType::OPEN_PUNCTUATION => {
-            ch.is_ascii().not() as usize + 1
-        }
-        WordType::RETURN => 0,
-        WordType::NEWLINE => 0,
-        WordType::SPACE => 1,
-        WordType::TAB => tab_width,
-        WordType::UNKNOWN => 0,
-        _ => ch.is_ascii().not() as usize + 1,
-    }
-}
-
 #[allow(unused)]
-impl Word<'_> {
-    pub fn new(text: &str, remaining_width: usize, tab_width: usize, letter_space: isize) -> Word {
+impl<'a> Word<'a> {
+    pub fn new(
+        text: &'a str,
+        remaining_width: usize,
+        tab_width: usize,
+        letter_space: isize,
+    ) -> Word<'a> {
         Word {
-            char_indices: text.char_indices().peekable(),
+            text,
+            graphemes: text.grapheme_indices(true).peekable(),
+            measurer: &DEFAULT_MEASURER,
+            strictness: LineBreakStrictness::Normal,
+            keep_all: false,
+            break_all: false,
+            dictionary: None,
+            dictionary_run_end: 0,
+            dictionary_breaks: vec![],
+            links: &NO_LINKS,
+            offset: 0,
+            tab_stops: &NO_TAB_STOPS,
+            white_space: WhiteSpace::default(),
+            column: 0,
             word_info_prev: None,
             remaining_width: remaining_width.saturating_add_signed(letter_space),
             tab_width,
             letter_space,
         }
     }
+}
+
+#[allow(unused)]
+impl<'a, M: WidthMeasurer> Word<'a, M> {
+    pub fn with_measurer<N: WidthMeasurer>(self, measurer: &'a N) -> Word<'a, N> {
+        Word {
+            text: self.text,
+            graphemes: self.graphemes,
+            measurer,
+            strictness: self.strictness,
+            keep_all: self.keep_all,
+            break_all: self.break_all,
+            dictionary: self.dictionary,
+            dictionary_run_end: self.dictionary_run_end,
+            dictionary_breaks: self.dictionary_breaks,
+            links: self.links,
+            offset: self.offset,
+            tab_stops: self.tab_stops,
+            white_space: self.white_space,
+            column: self.column,
+            word_info_prev: self.word_info_prev,
+            remaining_width: self.remaining_width,
+            tab_width: self.tab_width,
+            letter_space: self.letter_space,
+        }
+    }
+
+    pub fn with_strictness(mut self, strictness: LineBreakStrictness) -> Self {
+        self.strictness = strictness;
+        self
+    }
+
+    /// Keep runs of CJK characters together instead of breaking after each of them.
+    pub fn with_keep_all(mut self, keep_all: bool) -> Self {
+        self.keep_all = keep_all;
+        self
+    }
+
+    /// Break words of letters and digits after each grapheme, like CJK text.
+    pub fn with_break_all(mut self, break_all: bool) -> Self {
+        self.break_all = break_all;
+        self
+    }
+
+    /// Find the words of Thai, Lao, Khmer and Myanmar text with `dictionary` instead of the
+    /// embedded dictionary of each script.
+    pub fn with_dictionary(mut self, dictionary: Option<&'a Dictionary>) -> Self {
+        self.dictionary = dictionary;
+        self
+    }
+
+    /// Keep the URLs, e-mail addresses and paths of `links` together between their own break
+    /// opportunities, `offset` is where the text of the words starts in the text of `links`.
+    pub fn with_links(mut self, links: &'a TextLinks, offset: usize) -> Self {
+        self.links = links;
+        self.offset = offset;
+        self
+    }
+
+    /// Advance tabs to `tab_stops` instead of every `tab_width` columns.
+    pub fn with_tab_stops(mut self, tab_stops: &'a TabStops) -> Self {
+        self.tab_stops = tab_stops;
+        self
+    }
+
+    /// Collapse white space and turn line breaks into spaces as `white_space` says.
+    pub fn with_white_space(mut self, white_space: WhiteSpace) -> Self {
+        self.white_space = white_space;
+        self
+    }
 
     pub fn set_remaining_width(&mut self, remaining_width: usize) {
         self.remaining_width = remaining_width;
@@ -145,7 +397,85 @@ impl Word<'_> {
     }
 }
 
-impl Iterator for Word<'_> {
+impl<M: WidthMeasurer> Word<'_, M> {
+    /// Width of `grapheme`, which ends at `pos` and starts at `column`: a tab advances to the
+    /// next tab stop.
+    fn grapheme_width(&self, grapheme: &str, pos: usize, column: usize) -> usize {
+        // a run of collapsible white space is one space
+        if self.white_space.is_collapsible(grapheme) {
+            let prev = self.text[..pos - grapheme.len()]
+                .graphemes(true)
+                .next_back();
+            return match prev {
+                Some(prev) if self.white_space.is_collapsible(prev) => 0,
+                _ => self.measurer.grapheme_width(" ", self.tab_width),
+            };
+        }
+        if grapheme != "\t" {
+            return self.measurer.grapheme_width(grapheme, self.tab_width);
+        }
+        self.tab_stops
+            .advance(column, self.tab_width, &self.text[pos..], |v| {
+                v.graphemes(true)
+                    .map(|g| {
+                        self.measurer
+                            .grapheme_width(g, self.tab_width)
+                            .saturating_add_signed(self.letter_space)
+                    })
+                    .sum()
+            })
+    }
+
+    /// [`WordType::classify`], where tabs and line breaks are spaces when white space collapses
+    /// or line breaks are not kept.
+    fn classify(&self, grapheme: &str) -> WordType {
+        match WordType::classify(grapheme, self.strictness) {
+            WordType::TAB if self.white_space.collapses() => WordType::SPACE,
+            WordType::NEWLINE | WordType::RETURN if !self.white_space.keeps_line_breaks() => {
+                WordType::SPACE
+            }
+            word_type => word_type,
+        }
+    }
+
+    /// A currency sign or other prefix (PR) right before a digit, such as `¥` in `¥100`, or a
+    /// sign such as `-` in `-5` at the start of a word.
+    fn starts_number(&self, grapheme: &str, pos: usize) -> bool {
+        let Some(ch) = grapheme.chars().next() else {
+            return false;
+        };
+        let is_prefix = match LineBreakClass::from(ch) {
+            LineBreakClass::PR => true,
+            LineBreakClass::HY => self.word_info_prev.as_ref().is_none_or(|v| {
+                !matches!(
+                    v.word_type,
+                    WordType::LATIN | WordType::NUMBER | WordType::CJK | WordType::HYPHEN
+                )
+            }),
+            _ => false,
+        };
+        is_prefix && self.text[pos..].starts_with(|ch: char| ch.is_ascii_digit())
+    }
+
+    /// Whether a number goes on at `pos`, with more digits, a unit such as `km`, a postfix
+    /// (PO) such as `%`, or a separator followed by a digit as in `1,000.50`, `12:30`, `10/17`
+    /// and `2026-10-17`.
+    fn number_continues(&self, pos: usize) -> bool {
+        let mut chars = self.text[pos..].chars();
+        let Some(ch) = chars.next() else {
+            return false;
+        };
+        match LineBreakClass::from(ch) {
+            LineBreakClass::PO => true,
+            LineBreakClass::IS | LineBreakClass::SY | LineBreakClass::HY => {
+                chars.next().is_some_and(|ch| ch.is_ascii_digit())
+            }
+            _ => matches!(WordType::from(ch), WordType::NUMBER | WordType::LATIN),
+        }
+    }
+}
+
+impl<M: WidthMeasurer> Iterator for Word<'_, M> {
     type Item = WordInfo;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -154,44 +484,107 @@ impl Iterator for Word<'_> {
 
         let mut word_pos_end = start;
         let mut word_type = WordType::UNKNOWN;
+        // type of the run the word goes on with, which starts over after a no-break space
+        let mut run_type = WordType::UNKNOWN;
         let mut word_width = 0;
         let mut brk_pos = word_info_prev_ref.map_or(usize::MAX, |v| v.position.brk);
         let mut real_width = 0;
 
         loop {
-            let ch = self.char_indices.by_ref().peek()?.1;
-            let char_len = ch.len_utf8();
-            let char_width = get_char_width(ch, self.tab_width);
+            // words are made of whole extended grapheme clusters, so neither word boundaries
+            // nor forced breaks can split a base character from its combining marks
+            let grapheme = self.graphemes.by_ref().peek()?.1;
+            let grapheme_len = grapheme.len();
+            let grapheme_width = self.grapheme_width(
+                grapheme,
+                word_pos_end + grapheme_len,
+                self.column + word_width,
+            );
 
+            if word_pos_end == start && self.links.containing(self.offset + start).is_some() {
+                run_type = WordType::LINK;
+            }
+            if run_type == WordType::UNKNOWN {
+                run_type = self.classify(grapheme);
+                if self.starts_number(grapheme, word_pos_end + grapheme_len) {
+                    run_type = WordType::NUMBER;
+                }
+            }
             if word_type == WordType::UNKNOWN {
-                word_type = WordType::from(ch);
+                word_type = run_type.clone();
+            }
+
+            if word_pos_end >= self.dictionary_run_end
+                && grapheme.starts_with(dictionary::is_complex_context)
+            {
+                (self.dictionary_run_end, self.dictionary_breaks) =
+                    dictionary::run_breaks(self.text, word_pos_end, self.dictionary);
             }
 
-            self.char_indices.next();
+            self.graphemes.next();
+
+            let grapheme_next = self.graphemes.by_ref().peek().map_or("", |v| v.1);
+            let word_type_next = if self
+                .links
+                .is_link_start(self.offset + word_pos_end + grapheme_len)
+            {
+                WordType::LINK
+            } else {
+                self.classify(grapheme_next)
+            };
 
-            let char_next = self.char_indices.by_ref().peek().map_or(0 as char, |v| v.1);
-            let char_width_next = get_char_width(char_next, self.tab_width);
-            let word_type_next = WordType::from(char_next);
+            word_pos_end += grapheme_len;
+            word_width += grapheme_width.saturating_add_signed(self.letter_space);
+            let grapheme_width_next = self.grapheme_width(
+                grapheme_next,
+                word_pos_end + grapheme_next.len(),
+                self.column + word_width,
+            );
 
-            word_pos_end += char_len;
-            word_width += char_width.saturating_add_signed(self.letter_space);
+            if word_width + grapheme_width_next > self.remaining_width && brk_pos == usize::MAX {
+                brk_pos = word_pos_end;
+                real_width = word_width;
+            }
 
-            if word_width + char_width_next > self.remaining_width {
-                if brk_pos == usize::MAX {
-                    brk_pos = word_pos_end;
-                    real_width = word_width;
+            // no-break spaces, word joiners and ZWJ hold the graphemes on both sides together,
+            // a line may still break at a space or a tab next to them
+            let holds_next = grapheme.starts_with(is_glue) || grapheme.ends_with(ZWJ);
+            let is_space = |v: &WordType| {
+                matches!(
+                    v,
+                    WordType::SPACE | WordType::TAB | WordType::NEWLINE | WordType::RETURN
+                )
+            };
+            if !grapheme_next.is_empty()
+                && !is_space(&word_type_next)
+                && (holds_next || (grapheme_next.starts_with(is_glue) && !is_space(&run_type)))
+            {
+                if holds_next {
+                    run_type = WordType::UNKNOWN;
                 }
+                continue;
             }
 
-            match word_type {
+            match run_type {
                 WordType::LATIN => {
-                    if word_type_next == WordType::LATIN || word_type_next == WordType::NUMBER {
+                    if !self.break_all
+                        && (word_type_next == WordType::LATIN
+                            || word_type_next == WordType::NUMBER
+                            || word_type_next == WordType::SOFT_HYPHEN)
+                        && self.dictionary_breaks.binary_search(&word_pos_end).is_err()
+                    {
                         continue;
                     } else {
                         break;
                     }
                 }
                 WordType::CJK => {
+                    if self.keep_all && word_type_next == WordType::CJK {
+                        continue;
+                    }
+                    break;
+                }
+                WordType::EMOJI => {
                     break;
                 }
                 WordType::HYPHEN => {
@@ -202,7 +595,7 @@ impl Iterator for Word<'_> {
                     }
                 }
                 WordType::NUMBER => {
-                    if word_type_next == WordType::NUMBER {
+                    if !self.break_all && self.number_continues(word_pos_end) {
                         continue;
                     } else {
                         break;
@@ -222,11 +615,9 @@ impl Iterator for Word<'_> {
                         break;
                     }
                 }
-                WordType::RETURN => {
-                    break;
-                }
-                WordType::NEWLINE => {
-                    brk_pos = word_pos_end - 1;
+                // `\r\n` is a single grapheme, so it ends the line as one
+                WordType::RETURN | WordType::NEWLINE => {
+                    brk_pos = word_pos_end - grapheme_len;
                     break;
                 }
                 WordType::SPACE => {
@@ -235,7 +626,7 @@ impl Iterator for Word<'_> {
                     }
                     break;
                 }
-                WordType::TAB => {
+                WordType::TAB | WordType::SOFT_HYPHEN => {
                     break;
                 }
                 WordType::QUOTATION => {
@@ -244,6 +635,15 @@ impl Iterator for Word<'_> {
                     }
                     break;
                 }
+                WordType::LINK => {
+                    let pos = self.offset + word_pos_end;
+                    if self.links.containing(pos).is_some_and(|v| v.start < pos)
+                        && !self.links.is_break(pos)
+                    {
+                        continue;
+                    }
+                    break;
+                }
                 WordType::UNKNOWN => {
                     break;
                 }
@@ -257,6 +657,7 @@ impl Iterator for Word<'_> {
         if self.remaining_width >= real_width {
             self.remaining_width -= real_width;
         }
+        self.column += word_width;
 
         let info = WordInfo {
             position: WordPosition {
@@ -426,4 +827,243 @@ mod tests {
         assert_eq!(word.position.end, 5);
         assert_eq!(&text[word.position.start..word.position.brk], "    ");
     }
+
+    #[test]
+    fn test_8() {
+        let text = "cafe\u{301} ok".to_string();
+        let mut flow = Word::new(&text, 100, 4, 0);
+
+        let word = flow.next().unwrap();
+        assert_eq!(word.word_type, WordType::LATIN);
+        assert_eq!(word.real_width, 4);
+        assert_eq!(&text[word.position.start..word.position.end], "cafe\u{301}");
+
+        let text = "e\u{301}e\u{301}e\u{301}".to_string();
+        let mut flow = Word::new(&text, 2, 4, 0);
+
+        let word = flow.next().unwrap();
+        assert_eq!(word.position.brk, 6);
+        assert_eq!(
+            &text[word.position.start..word.position.brk],
+            "e\u{301}e\u{301}"
+        );
+    }
+
+    #[test]
+    fn test_9() {
+        for emoji in [
+            "😀",
+            "👍🏽",
+            "🇨🇳",
+            "1\u{fe0f}\u{20e3}",
+            "#\u{20e3}",
+            "❤\u{fe0f}",
+            "👨\u{200d}👩\u{200d}👧",
+            "⌚",
+        ] {
+            assert_eq!(WordType::from(emoji), WordType::EMOJI, "{:?}", emoji);
+        }
+
+        for text in ["❤", "☺\u{fe0e}", "©", "1", "中"] {
+            assert_ne!(WordType::from(text), WordType::EMOJI, "{:?}", text);
+        }
+
+        let text = "👍🏽🇨🇳".to_string();
+        let mut flow = Word::new(&text, 100, 4, 0);
+
+        let word = flow.next().unwrap();
+        assert_eq!(word.word_type, WordType::EMOJI);
+        assert_eq!(word.real_width, 2);
+        assert_eq!(&text[word.position.start..word.position.end], "👍🏽");
+
+        let word = flow.next().unwrap();
+        assert_eq!(word.word_type, WordType::EMOJI);
+        assert_eq!(word.real_width, 2);
+        assert_eq!(&text[word.position.start..word.position.end], "🇨🇳");
+
+        assert_eq!(flow.next(), None);
+    }
+
+    #[test]
+    fn test_10() {
+        let text = "hy\u{ad}phen \u{ad}";
+        let words = Word::new(text, 100, 4, 0).collect::<Vec<_>>();
+        assert_eq!(words.len(), 3);
+        assert_eq!(words[0].word_type, WordType::LATIN);
+        assert_eq!(
+            &text[words[0].position.start..words[0].position.end],
+            "hy\u{ad}phen"
+        );
+        assert_eq!(words[0].ideal_width, 6);
+        assert_eq!(words[2].word_type, WordType::SOFT_HYPHEN);
+    }
+
+    #[test]
+    fn test_11() {
+        let text = "你好世界，再见";
+        let words = Word::new(text, 100, 4, 0)
+            .with_keep_all(true)
+            .map(|v| &text[v.position.start..v.position.end])
+            .collect::<Vec<_>>();
+        assert_eq!(words, ["你好世界", "，", "再见"]);
+    }
+
+    #[test]
+    fn test_12() {
+        let measurer = MonospaceMeasurer::default();
+        let corpus = [
+            ("hiragana", "ひらがなぁゖ", 2),
+            ("katakana", "カタカナァヶー", 2),
+            ("halfwidth katakana", "ｶﾀｶﾅ", 1),
+            ("hangul syllables", "한국어가힣", 2),
+            ("hangul jamo", "ᄀᄁㄱㅏ", 2),
+            ("bopomofo", "ㄅㄆㄇㄈㆠ", 2),
+            ("cjk symbols", "〆〇〒", 2),
+            ("cjk extension a", "㐀㐁䶿", 2),
+            ("cjk extension b - h", "𠀀𪜀𫝀𫠠𬺰𰀀𱍐", 2),
+            ("cjk compatibility ideographs", "豈更﨩", 2),
+            ("kana supplement", "𛀀𛀁", 2),
+        ];
+
+        for (script, text, width) in corpus {
+            for ch in text.chars() {
+                assert_eq!(WordType::from(ch), WordType::CJK, "{script}: {ch:?}");
+                assert_eq!(measurer.char_width(ch, 4), width, "{script}: {ch:?}");
+            }
+        }
+
+        // punctuation in the cjk blocks keeps its kinsoku class
+        assert_eq!(WordType::from('。'), WordType::CLOSE_PUNCTUATION);
+        assert_eq!(WordType::from('「'), WordType::OPEN_PUNCTUATION);
+    }
+
+    #[test]
+    fn test_13() {
+        let text = "café Straße Привет Καλημέρα Բարեւ გამარჯობა cafe\u{301}";
+        let words = Word::new(text, 100, 4, 0)
+            .filter(|v| v.word_type != WordType::SPACE)
+            .collect::<Vec<_>>();
+        let slices = words
+            .iter()
+            .map(|v| &text[v.position.start..v.position.end])
+            .collect::<Vec<_>>();
+        assert_eq!(
+            slices,
+            [
+                "café",
+                "Straße",
+                "Привет",
+                "Καλημέρα",
+                "Բարեւ",
+                "გამარჯობა",
+                "cafe\u{301}"
+            ]
+        );
+        assert!(words.iter().all(|v| v.word_type == WordType::LATIN));
+        let widths = words.iter().map(|v| v.ideal_width).collect::<Vec<_>>();
+        assert_eq!(widths, [4, 6, 6, 8, 5, 9, 4]);
+    }
+
+    #[test]
+    fn test_14() {
+        let text = "ราคาถูก ส่งฟรี";
+        let slices = |words: Word| {
+            words
+                .map(|v| &text[v.position.start..v.position.end])
+                .collect::<Vec<_>>()
+        };
+        #[cfg(feature = "thai")]
+        assert_eq!(
+            slices(Word::new(text, 100, 4, 0)),
+            ["ราคา", "ถูก", " ", "ส่ง", "ฟรี"]
+        );
+
+        let dictionary = Dictionary::from_words("ราคาถูก ส่ง");
+        assert_eq!(
+            slices(Word::new(text, 100, 4, 0).with_dictionary(Some(&dictionary))),
+            ["ราคาถูก", " ", "ส่งฟรี"]
+        );
+    }
+
+    #[test]
+    fn test_15() {
+        let text = "¥100 100% 3.14 1,000.50 2026-10-17 12:30 10/17 -5°C 100km 3. well-10";
+        let slices = Word::new(text, 100, 4, 0)
+            .filter(|v| v.word_type != WordType::SPACE)
+            .map(|v| (&text[v.position.start..v.position.end], v.word_type))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            slices,
+            [
+                ("¥100", WordType::NUMBER),
+                ("100%", WordType::NUMBER),
+                ("3.14", WordType::NUMBER),
+                ("1,000.50", WordType::NUMBER),
+                ("2026-10-17", WordType::NUMBER),
+                ("12:30", WordType::NUMBER),
+                ("10/17", WordType::NUMBER),
+                ("-5°C", WordType::NUMBER),
+                ("100km", WordType::NUMBER),
+                ("3", WordType::NUMBER),
+                (".", WordType::CLOSE_PUNCTUATION),
+                ("well", WordType::LATIN),
+                ("-", WordType::HYPHEN),
+                ("10", WordType::NUMBER),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_16() {
+        let text = "see (https://example.com/a?b=1), ok";
+        let links = TextLinks::new(text, false);
+        let slices = Word::new(&text[4..], 100, 4, 0)
+            .with_links(&links, 4)
+            .map(|v| (&text[4 + v.position.start..4 + v.position.end], v.word_type))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            slices,
+            [
+                ("(", WordType::OPEN_PUNCTUATION),
+                ("https://example", WordType::LINK),
+                (".com/", WordType::LINK),
+                ("a", WordType::LINK),
+                ("?b=1", WordType::LINK),
+                ("),", WordType::CLOSE_PUNCTUATION),
+                (" ", WordType::SPACE),
+                ("ok", WordType::LATIN),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_17() {
+        let text = "Mr.\u{a0}Smith 5\u{202f}% a\u{200b}b 中\u{2060}文 \u{a0}x";
+        let slices = Word::new(text, 100, 4, 0)
+            .map(|v| {
+                (
+                    &text[v.position.start..v.position.end],
+                    v.word_type,
+                    v.ideal_width,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            slices,
+            [
+                ("Mr", WordType::LATIN, 2),
+                (".\u{a0}Smith", WordType::CLOSE_PUNCTUATION, 7),
+                (" ", WordType::SPACE, 1),
+                ("5\u{202f}%", WordType::NUMBER, 3),
+                (" ", WordType::SPACE, 1),
+                ("a", WordType::LATIN, 1),
+                ("\u{200b}", WordType::SPACE, 0),
+                ("b", WordType::LATIN, 1),
+                (" ", WordType::SPACE, 1),
+                ("中\u{2060}文", WordType::CJK, 4),
+                (" ", WordType::SPACE, 1),
+                ("\u{a0}x", WordType::LATIN, 2),
+            ]
+        );
+    }
 }
@@ -3,7 +3,11 @@ extern crate textflow;
 
 #[cfg(test)]
 mod testcase_suit_1 {
-    use textflow::TextFlow;
+    use textflow::{
+        AmbiguousWidth, BreakEngine, BreakKind, BreakOpportunities, Dictionary, HangingPunctuation,
+        Hyphenator, LineBreakStrictness, LineTerminator, LinkKind, OrphanControl, SpacingTrim,
+        TabStop, TabStops, TextFlow, WhiteSpace, WidthMeasurer, WordBreak, WrapMode,
+    };
 
     #[test]
     fn test_1() {
@@ -280,4 +284,1230 @@ mod testcase_suit_1 {
             "\"various"
         );
     }
+
+    #[test]
+    fn test_25() {
+        struct DoubleMeasurer;
+
+        impl WidthMeasurer for DoubleMeasurer {
+            fn char_width(&self, ch: char, _tab_width: usize) -> usize {
+                if ch == '\n' {
+                    0
+                } else {
+                    2
+                }
+            }
+        }
+
+        let text = "Hello,world!";
+        let mut flow = TextFlow::new(text, 20).with_measurer(DoubleMeasurer);
+
+        let line = flow.next().unwrap();
+        assert_eq!(line.slices(text), "Hello,");
+        assert_eq!(line.real_width, 12);
+        assert_line!(text, flow.next(), Some("world!"));
+        assert_line!(text, flow.next(), None);
+    }
+
+    #[test]
+    fn test_26() {
+        let text = "±1 ±2";
+
+        let mut flow = TextFlow::new(text, 5);
+        let line = flow.next().unwrap();
+        assert_eq!(line.slices(text), "±1 ±2");
+        assert_eq!(line.real_width, 5);
+
+        let mut flow = TextFlow::new(text, 5).with_ambiguous_width(AmbiguousWidth::Wide);
+        let line = flow.next().unwrap();
+        assert_eq!(line.slices(text), "±1");
+        assert_eq!(line.real_width, 3);
+        assert_line!(text, flow.next(), Some("±2"));
+    }
+
+    #[test]
+    fn test_27() {
+        assert_flow!(
+            "e\u{301}e\u{301}e\u{301}"
+            => 2 =>
+            "e\u{301}e\u{301}"
+            "e\u{301}"
+        );
+
+        assert_flow!(
+            "👨\u{200d}👩\u{200d}👧👨\u{200d}👩\u{200d}👧"
+            => 3 =>
+            "👨\u{200d}👩\u{200d}👧"
+            "👨\u{200d}👩\u{200d}👧"
+        );
+    }
+
+    #[test]
+    fn test_28() {
+        assert_flow!(
+            "😀😀😀👍🏽🇨🇳"
+            => 4 =>
+            "😀😀"
+            "😀👍🏽"
+            "🇨🇳"
+        );
+
+        assert_flow!(
+            "好的😀！收到"
+            => 6 =>
+            "好的"
+            "😀！收"
+            "到"
+        );
+    }
+
+    #[test]
+    fn test_29() {
+        let text = "The quick brown fox jumps over a lazy dog.";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 15).with_break_engine(BreakEngine::Uax14)
+            =>
+            "The quick brown"
+            "fox jumps over"
+            "a lazy dog."
+        );
+
+        let text = "价格：¥100，折扣20%。好的";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 10).with_break_engine(BreakEngine::Uax14)
+            =>
+            "价格："
+            "¥100，折扣"
+            "20%。好的"
+        );
+
+        let text = "Hello, world!";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 3).with_break_engine(BreakEngine::Uax14)
+            =>
+            "Hel"
+            "lo,"
+            "wor"
+            "ld!"
+        );
+    }
+
+    #[test]
+    fn test_30() {
+        let text = "aaa bb cc ddddd";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 6)
+            =>
+            "aaa bb"
+            "cc"
+            "ddddd"
+        );
+        assert_lines!(
+            text,
+            TextFlow::new(text, 6).with_wrap_mode(WrapMode::Optimal)
+            =>
+            "aaa"
+            "bb cc"
+            "ddddd"
+        );
+
+        let text = "ab  \ncd ef gh";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 5)
+                .with_break_engine(BreakEngine::Uax14)
+                .with_wrap_mode(WrapMode::Optimal)
+            =>
+            "ab"
+            "cd ef"
+            "gh"
+        );
+    }
+
+    #[test]
+    fn test_31() {
+        let text = "The quick brown fox jumps over a lazy dog.";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 30).with_wrap_mode(WrapMode::Balance)
+            =>
+            "The quick brown fox"
+            "jumps over a lazy dog."
+        );
+
+        let text = "八百标兵奔北坡炮兵并排北边跑";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 20).with_wrap_mode(WrapMode::Balance)
+            =>
+            "八百标兵奔北坡"
+            "炮兵并排北边跑"
+        );
+
+        let text = "Short title";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 30).with_wrap_mode(WrapMode::Balance)
+            =>
+            "Short title"
+        );
+    }
+
+    #[test]
+    fn test_32() {
+        let text = "八百标兵奔北坡炮兵并排北边跑。";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 14)
+            =>
+            "八百标兵奔北坡"
+            "炮兵并排北边"
+            "跑。"
+        );
+        assert_lines!(
+            text,
+            TextFlow::new(text, 14).with_wrap_mode(WrapMode::Pretty)
+            =>
+            "八百标兵奔北"
+            "坡炮兵并排北"
+            "边跑。"
+        );
+
+        let text = "The quick brown fox jumps over a lazy dog.";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 20)
+                .with_wrap_mode(WrapMode::Pretty)
+                .with_orphan_control(OrphanControl {
+                    min_words: 1,
+                    min_fill: 0.5,
+                    look_back: 1,
+                })
+            =>
+            "The quick brown fox"
+            "jumps over"
+            "a lazy dog."
+        );
+    }
+
+    #[test]
+    fn test_33() {
+        let text = "The hyphenation algorithm of Liang finds acceptable breakpoints.";
+        let lines = TextFlow::new(text, 20)
+            .with_hyphenator(Hyphenator::en_us())
+            .collect::<Vec<_>>();
+        let slices = lines.iter().map(|v| v.slices(text)).collect::<Vec<_>>();
+        assert_eq!(
+            slices,
+            [
+                "The hyphenation al",
+                "gorithm of Liang",
+                "finds acceptable",
+                "breakpoints."
+            ]
+        );
+        assert!(lines[0].hyphenated);
+        assert_eq!(lines[0].real_width, 19);
+        assert_eq!(lines[0].ideal_width, 18);
+        assert!(!lines[1].hyphenated);
+
+        let hyphenator = Hyphenator::from_tex("\\patterns{1ba}").with_min(1, 1);
+        let text = "abababab";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 5).with_hyphenator(&hyphenator)
+            =>
+            "aba"
+            "babab"
+        );
+    }
+
+    #[test]
+    fn test_34() {
+        let text = "The hy\u{ad}phen\u{ad}ation algo\u{ad}rithm";
+        let lines = TextFlow::new(text, 10).collect::<Vec<_>>();
+        let slices = lines.iter().map(|v| v.slices(text)).collect::<Vec<_>>();
+        assert_eq!(slices, ["The hy", "phen\u{ad}ation", "algo\u{ad}rithm"]);
+        assert!(lines[0].hyphenated);
+        assert_eq!(lines[0].real_width, 7);
+        assert_eq!(lines[1].real_width, 9);
+        assert!(!lines[1].hyphenated);
+
+        assert_lines!(
+            text,
+            TextFlow::new(text, 10).with_break_engine(BreakEngine::Uax14)
+            =>
+            "The hy"
+            "phen\u{ad}ation"
+            "algo\u{ad}rithm"
+        );
+    }
+
+    #[test]
+    fn test_35() {
+        let text = "中文测试ー中文人々中文：中文";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 8)
+            =>
+            "中文测试"
+            "ー中文"
+            "人々中"
+            "文：中文"
+        );
+        assert_lines!(
+            text,
+            TextFlow::new(text, 8).with_line_break_strictness(LineBreakStrictness::Strict)
+            =>
+            "中文测"
+            "试ー中文"
+            "人々中"
+            "文：中文"
+        );
+        assert_lines!(
+            text,
+            TextFlow::new(text, 8).with_line_break_strictness(LineBreakStrictness::Loose)
+            =>
+            "中文测试"
+            "ー中文人"
+            "々中文："
+            "中文"
+        );
+
+        // Latin dashes break after and not before them whatever the strictness
+        let text = "x ab–cd";
+        for strictness in [
+            LineBreakStrictness::Loose,
+            LineBreakStrictness::Normal,
+            LineBreakStrictness::Strict,
+        ] {
+            for engine in [BreakEngine::Heuristic, BreakEngine::Uax14] {
+                assert_lines!(
+                    text,
+                    TextFlow::new(text, 4)
+                        .with_break_engine(engine)
+                        .with_line_break_strictness(strictness)
+                    =>
+                    "x"
+                    "ab–"
+                    "cd"
+                );
+            }
+        }
+
+        let text = "say (hi), ok";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 4).with_line_break_strictness(LineBreakStrictness::Anywhere)
+            =>
+            "say"
+            "(hi)"
+            ", ok"
+        );
+    }
+
+    #[test]
+    fn test_36() {
+        let text = "我们保持词语 完整不拆开";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 16)
+            =>
+            "我们保持词语 完"
+            "整不拆开"
+        );
+        assert_lines!(
+            text,
+            TextFlow::new(text, 16).with_word_break(WordBreak::KeepAll)
+            =>
+            "我们保持词语"
+            "完整不拆开"
+        );
+        // runs longer than the line fall back to long word breaking
+        assert_lines!(
+            text,
+            TextFlow::new(text, 8).with_word_break(WordBreak::KeepAll)
+            =>
+            "我们保持"
+            "词语"
+            "完整不拆"
+            "开"
+        );
+
+        let text = "한국어 텍스트는 띄어쓰기로 나눕니다";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 12)
+                .with_break_engine(BreakEngine::Uax14)
+                .with_word_break(WordBreak::KeepAll)
+            =>
+            "한국어"
+            "텍스트는"
+            "띄어쓰기로"
+            "나눕니다"
+        );
+    }
+
+    #[test]
+    fn test_37() {
+        let text = "あいうっえお";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 6)
+            =>
+            "あいう"
+            "っえお"
+        );
+        assert_lines!(
+            text,
+            TextFlow::new(text, 6).with_line_break_strictness(LineBreakStrictness::Strict)
+            =>
+            "あい"
+            "うっえ"
+            "お"
+        );
+
+        let text = "きょうはいいてんきですね。カタカナもあります";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 10)
+            =>
+            "きょうはい"
+            "いてんきで"
+            "すね。カタ"
+            "カナもあり"
+            "ます"
+        );
+
+        let text = "ｶﾀｶﾅﾃｷｽﾄ";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 5)
+            =>
+            "ｶﾀｶﾅﾃ"
+            "ｷｽﾄ"
+        );
+    }
+
+    #[test]
+    fn test_38() {
+        let text = "안녕하세요 세계 여러분";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 10)
+            =>
+            "안녕하세요"
+            "세계 여러"
+            "분"
+        );
+        assert_lines!(
+            text,
+            TextFlow::new(text, 10).with_word_break(WordBreak::KeepAll)
+            =>
+            "안녕하세요"
+            "세계"
+            "여러분"
+        );
+    }
+
+    #[test]
+    fn test_39() {
+        let text = "𠀀𠀁𠀂𪜀𫝀𫠠𬺰𰀀";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 10)
+            =>
+            "𠀀𠀁𠀂𪜀𫝀"
+            "𫠠𬺰𰀀"
+        );
+
+        let text = "ㄅㄆㄇㄈ㐀㐁豈更";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 10)
+            =>
+            "ㄅㄆㄇㄈ㐀"
+            "㐁豈更"
+        );
+    }
+
+    #[test]
+    fn test_40() {
+        let text = "Le café de la Straße est fermé.";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 12)
+            =>
+            "Le café de"
+            "la Straße"
+            "est fermé."
+        );
+
+        let text = "Съешь же ещё этих мягких французских булок";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 16)
+            =>
+            "Съешь же ещё"
+            "этих мягких"
+            "французских"
+            "булок"
+        );
+
+        let text = "Καλημέρα κόσμε, τι κάνεις;";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 10)
+            =>
+            "Καλημέρα"
+            "κόσμε, τι"
+            "κάνεις;"
+        );
+
+        let text = "Բարեւ աշխարհ გამარჯობა მსოფლიო";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 12)
+            =>
+            "Բարեւ աշխարհ"
+            "გამარჯობა"
+            "მსოფლიო"
+        );
+    }
+
+    #[test]
+    fn test_41() {
+        let text = "สินค้าคุณภาพดีราคาถูกจัดส่งฟรีทั่วประเทศ";
+        #[cfg(feature = "thai")]
+        for engine in [BreakEngine::Heuristic, BreakEngine::Uax14] {
+            assert_lines!(
+                text,
+                TextFlow::new(text, 12).with_break_engine(engine)
+                =>
+                "สินค้าคุณภาพดี"
+                "ราคาถูกจัดส่งฟรี"
+                "ทั่วประเทศ"
+            );
+        }
+
+        // without the embedded word lists a run that fits does not break
+        #[cfg(not(feature = "thai"))]
+        assert_lines!(
+            text,
+            TextFlow::new(text, 40)
+            =>
+            "สินค้าคุณภาพดีราคาถูกจัดส่งฟรีทั่วประเทศ"
+        );
+
+        #[cfg(feature = "lao")]
+        let text = "ພາສາລາວເປັນພາສາທີ່ສວຍງາມ";
+        #[cfg(feature = "lao")]
+        assert_lines!(
+            text,
+            TextFlow::new(text, 8)
+            =>
+            "ພາສາລາວ"
+            "ເປັນພາສາທີ່"
+            "ສວຍງາມ"
+        );
+
+        let dictionary = Dictionary::from_words("สินค้า คุณภาพดี ราคาถูก จัดส่งฟรี ทั่วประเทศ");
+        let text = "สินค้าคุณภาพดีราคาถูกจัดส่งฟรีทั่วประเทศ";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 8).with_dictionary(&dictionary)
+            =>
+            "สินค้า"
+            "คุณภาพดี"
+            "ราคาถูก"
+            "จัดส่งฟรี"
+            "ทั่วประเทศ"
+        );
+    }
+
+    #[test]
+    fn test_42() {
+        let text = "Total ¥1,000.50 (15% off) on 2026-10-17 at 12:30.";
+        for engine in [BreakEngine::Heuristic, BreakEngine::Uax14] {
+            assert_lines!(
+                text,
+                TextFlow::new(text, 10).with_break_engine(engine)
+                =>
+                "Total"
+                "¥1,000.50"
+                "(15% off)"
+                "on"
+                "2026-10-17"
+                "at 12:30."
+            );
+        }
+
+        let text = "It costs $3.99, about 12.5% more.";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 12).with_wrap_mode(WrapMode::Optimal)
+            =>
+            "It costs"
+            "$3.99, about"
+            "12.5% more."
+        );
+    }
+
+    #[test]
+    fn test_43() {
+        let text = "Docs at https://docs.example.com/guide/install?os=linux#req, or mail support@example.com.";
+        for engine in [BreakEngine::Heuristic, BreakEngine::Uax14] {
+            assert_lines!(
+                text,
+                TextFlow::new(text, 24)
+                    .with_break_engine(engine)
+                    .with_link_detection(true)
+                =>
+                "Docs at https://docs"
+                ".example.com/guide/"
+                "install?os=linux#req, or"
+                "mail support@example"
+                ".com."
+            );
+        }
+
+        assert_lines!(
+            text,
+            TextFlow::new(text, 26)
+                .with_keep_link_hosts(true)
+                .with_link_detection(true)
+            =>
+            "Docs at"
+            "https://docs.example.com/"
+            "guide/install?os=linux"
+            "#req, or mail support"
+            "@example.com."
+        );
+
+        // the links are reported whole on every line they are shown on
+        let links = TextFlow::new(text, 24)
+            .with_link_detection(true)
+            .map(|v| {
+                v.links
+                    .iter()
+                    .map(|v| (v.kind, v.slices(text)))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let url = (
+            LinkKind::Url,
+            "https://docs.example.com/guide/install?os=linux#req",
+        );
+        let email = (LinkKind::Email, "support@example.com");
+        assert_eq!(
+            links,
+            [vec![url], vec![url], vec![url], vec![email], vec![email]]
+        );
+
+        // without link detection the text breaks like prose
+        let lines = TextFlow::new(text, 24).collect::<Vec<_>>();
+        assert_eq!(lines[0].slices(text), "Docs at https://docs.");
+        assert!(lines.iter().all(|v| v.links.is_empty()));
+
+        let text = "Edit ~/.config/textflow/settings.toml then run /usr/local/bin/textflow";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 20).with_link_detection(true)
+            =>
+            "Edit ~/.config/"
+            "textflow/"
+            "settings.toml then"
+            "run /usr/local/bin/"
+            "textflow"
+        );
+    }
+
+    #[test]
+    fn test_44() {
+        let text = "「春眠不觉晓，处处闻啼鸟。夜来风雨声，花落知多少。」";
+        let hanging = HangingPunctuation {
+            first: true,
+            last: true,
+            allow_end: true,
+        };
+        assert_lines!(
+            text,
+            TextFlow::new(text, 10).with_hanging_punctuation(hanging)
+            =>
+            "「春眠不觉晓，"
+            "处处闻啼鸟。"
+            "夜来风雨声，"
+            "花落知多"
+            "少。」"
+        );
+
+        // the lines are no wider than 10 without what hangs
+        let hangs = TextFlow::new(text, 10)
+            .with_hanging_punctuation(hanging)
+            .map(|v| (v.hang_start, v.real_width, v.hang_end))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            hangs,
+            [(2, 14, 2), (0, 12, 2), (0, 12, 2), (0, 8, 0), (0, 6, 0)]
+        );
+
+        let text = "Tell them, please.";
+        let hanging = HangingPunctuation {
+            allow_end: true,
+            ..HangingPunctuation::default()
+        };
+        assert_lines!(
+            text,
+            TextFlow::new(text, 9)
+            =>
+            "Tell"
+            "them,"
+            "please."
+        );
+        assert_lines!(
+            text,
+            TextFlow::new(text, 9).with_hanging_punctuation(hanging)
+            =>
+            "Tell them,"
+            "please."
+        );
+    }
+
+    #[test]
+    fn test_45() {
+        let text = "「今日は、「晴れ」。」と言った。明日も（たぶん）晴れ。";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 12)
+            =>
+            "「今日は、"
+            "「晴れ」。」"
+            "と言った。明"
+            "日も（たぶ"
+            "ん）晴れ。"
+        );
+        // `」。」` takes 4 cells, so `と` fits after it
+        for engine in [BreakEngine::Heuristic, BreakEngine::Uax14] {
+            assert_lines!(
+                text,
+                TextFlow::new(text, 12)
+                    .with_break_engine(engine)
+                    .with_spacing_trim(SpacingTrim::Normal)
+                =>
+                "「今日は、"
+                "「晴れ」。」と"
+                "言った。明日"
+                "も（たぶん）"
+                "晴れ。"
+            );
+        }
+
+        // `、` and `）` end their lines half width, `「` starts its line half width
+        let widths = |trim| {
+            TextFlow::new(text, 12)
+                .with_spacing_trim(trim)
+                .map(|v| v.real_width)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(widths(SpacingTrim::Normal), [9, 12, 12, 11, 5]);
+        assert_eq!(widths(SpacingTrim::TrimStart), [8, 11, 12, 11, 5]);
+    }
+
+    #[test]
+    fn test_46() {
+        let text = "用Rust写的TextFlow库，支持3种模式。";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 12)
+            =>
+            "用Rust写的"
+            "TextFlow库，"
+            "支持3种模"
+            "式。"
+        );
+        // the gaps count, but not the one at a line end
+        for engine in [BreakEngine::Heuristic, BreakEngine::Uax14] {
+            assert_lines!(
+                text,
+                TextFlow::new(text, 12)
+                    .with_break_engine(engine)
+                    .with_autospace(1)
+                =>
+                "用Rust写的"
+                "TextFlow"
+                "库，支持3"
+                "种模式。"
+            );
+        }
+
+        let gaps = TextFlow::new(text, 12)
+            .with_autospace(1)
+            .map(|v| (v.real_width, v.autospace))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            gaps,
+            [(12, vec![3, 7]), (8, vec![]), (10, vec![33]), (8, vec![])]
+        );
+    }
+
+    #[test]
+    fn test_47() {
+        let text = "It is 100\u{a0}km from Mr.\u{a0}Smith";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 10)
+            =>
+            "It is"
+            "100\u{a0}km"
+            "from"
+            "Mr.\u{a0}Smith"
+        );
+
+        // a zero width space is a break opportunity, a word joiner is none
+        let text = "compound\u{200b}words and 中\u{2060}文\u{2060}字";
+        for engine in [BreakEngine::Heuristic, BreakEngine::Uax14] {
+            assert_lines!(
+                text,
+                TextFlow::new(text, 9).with_break_engine(engine)
+                =>
+                "compound\u{200b}"
+                "words and"
+                "中\u{2060}文\u{2060}字"
+            );
+        }
+
+        let widths = TextFlow::new("a\u{a0}b\u{202f}c\u{200b}d", 10)
+            .map(|v| v.real_width)
+            .collect::<Vec<_>>();
+        assert_eq!(widths, [6]);
+    }
+
+    #[test]
+    fn test_48() {
+        // like `expand(1)`: a tab advances to the next multiple of 8 from the line start
+        let text = "id\tname\tsize\n1\tfoo\t12\n22\tlonger name\t3456";
+        for engine in [BreakEngine::Heuristic, BreakEngine::Uax14] {
+            let lines = TextFlow::new(text, 20)
+                .with_break_engine(engine)
+                .with_tab_width(8)
+                .map(|v| (v.slices(text).to_string(), v.real_width))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                lines[..2],
+                [
+                    ("id\tname\tsize".to_string(), 20),
+                    ("1\tfoo\t12".to_string(), 18),
+                ]
+            );
+        }
+        let widths = TextFlow::new(text, 20)
+            .with_tab_width(4)
+            .map(|v| v.real_width)
+            .collect::<Vec<_>>();
+        assert_eq!(widths, [16, 10, 20]);
+        // without a tab width, tabs only advance to explicit stops
+        let widths = TextFlow::new(text, 20)
+            .map(|v| v.real_width)
+            .collect::<Vec<_>>();
+        assert_eq!(widths, [10, 6, 17]);
+
+        // a left, a right and a decimal stop
+        let text = "a\tb\tc\t1.5\nccc\tdd\tee\t12.25";
+        let stops = TabStops::new([TabStop::left(4), TabStop::right(10), TabStop::decimal(16)]);
+        for engine in [BreakEngine::Heuristic, BreakEngine::Uax14] {
+            let widths = TextFlow::new(text, 20)
+                .with_break_engine(engine)
+                .with_tab_stops(stops.clone())
+                .map(|v| v.real_width)
+                .collect::<Vec<_>>();
+            assert_eq!(widths, [18, 19]);
+        }
+    }
+
+    #[test]
+    fn test_49() {
+        let text = "  Hello   world,\n  this  is\t\tHTML.   \nEnd";
+        let lines = |white_space| {
+            [BreakEngine::Heuristic, BreakEngine::Uax14].map(|engine| {
+                TextFlow::new(text, 10)
+                    .with_break_engine(engine)
+                    .with_white_space(white_space)
+                    .with_tab_width(8)
+                    .map(|v| (v.slices(text), v.real_width))
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        // collapsed white space takes the width of one space, positions are still those of
+        // the text
+        for lines in lines(WhiteSpace::Normal) {
+            assert_eq!(
+                lines,
+                [
+                    ("Hello", 5),
+                    ("world,", 6),
+                    ("this  is", 7),
+                    ("HTML.   \nEnd", 9)
+                ]
+            );
+        }
+        for lines in lines(WhiteSpace::NoWrap) {
+            assert_eq!(lines, [(&text[2..], 30)]);
+        }
+        // lines that never wrap keep their trailing spaces
+        for lines in lines(WhiteSpace::Pre) {
+            assert_eq!(
+                lines,
+                [
+                    ("  Hello   world,", 16),
+                    ("  this  is\t\tHTML.   ", 32),
+                    ("End", 3)
+                ]
+            );
+        }
+        for lines in lines(WhiteSpace::PreLine) {
+            assert_eq!(
+                lines,
+                [
+                    ("Hello", 5),
+                    ("world,", 6),
+                    ("this  is", 7),
+                    ("HTML.", 5),
+                    ("End", 3)
+                ]
+            );
+        }
+
+        // trailing spaces take their width and wrap
+        let text = "aaa   bbb cc";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 5)
+            =>
+            "aaa"
+            "bbb"
+            "cc"
+        );
+        assert_lines!(
+            text,
+            TextFlow::new(text, 5).with_white_space(WhiteSpace::BreakSpaces)
+            =>
+            "aaa  "
+            " bbb "
+            "cc"
+        );
+    }
+
+    #[test]
+    fn test_50() {
+        let text = "ab\r\ncd\ref\ngh\u{2028}ij\u{2029}kl\u{b}mn\u{c}op\u{85}qr";
+        for engine in [BreakEngine::Heuristic, BreakEngine::Uax14] {
+            for wrap_mode in [WrapMode::Greedy, WrapMode::Optimal] {
+                let lines = TextFlow::new(text, 10)
+                    .with_break_engine(engine)
+                    .with_wrap_mode(wrap_mode)
+                    .collect::<Vec<_>>();
+                assert_eq!(
+                    lines.iter().map(|v| v.slices(text)).collect::<Vec<_>>(),
+                    ["ab", "cd", "ef", "gh", "ij", "kl", "mn", "op", "qr"]
+                );
+                assert_eq!(
+                    lines.iter().map(|v| v.terminator).collect::<Vec<_>>(),
+                    [
+                        Some(LineTerminator::CrLf),
+                        Some(LineTerminator::Cr),
+                        Some(LineTerminator::Lf),
+                        Some(LineTerminator::LineSeparator),
+                        Some(LineTerminator::ParagraphSeparator),
+                        Some(LineTerminator::VerticalTab),
+                        Some(LineTerminator::FormFeed),
+                        Some(LineTerminator::Nel),
+                        None
+                    ]
+                );
+                // the lines and their terminators make up the text again
+                let joined: String = lines
+                    .iter()
+                    .map(|v| v.slices(text).to_string() + v.terminator.map_or("", |v| v.as_str()))
+                    .collect();
+                assert_eq!(joined, text);
+            }
+        }
+
+        // `\r\n` is one break, there is no empty line between
+        let text = "ab\r\n\r\ncd";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 10)
+            =>
+            "ab"
+            ""
+            "cd"
+        );
+    }
+
+    #[test]
+    fn test_51() {
+        let text = "Hello, hyphenation!\n「漢字」です。";
+        let hyphenator = Hyphenator::en_us();
+        for engine in [BreakEngine::Heuristic, BreakEngine::Uax14] {
+            let breaks = TextFlow::new(text, 10)
+                .with_break_engine(engine)
+                .with_hyphenator(hyphenator)
+                .break_opportunities()
+                .collect::<Vec<_>>();
+            assert_eq!(
+                breaks
+                    .iter()
+                    .map(|v| (&text[..v.position], v.kind))
+                    .collect::<Vec<_>>(),
+                [
+                    ("Hello, ", BreakKind::Allowed),
+                    ("Hello, hy", BreakKind::AllowedWithHyphen),
+                    ("Hello, hyphen", BreakKind::AllowedWithHyphen),
+                    ("Hello, hyphenation!\n", BreakKind::Mandatory),
+                    ("Hello, hyphenation!\n「", BreakKind::ProhibitedByKinsoku),
+                    ("Hello, hyphenation!\n「漢", BreakKind::Allowed),
+                    (
+                        "Hello, hyphenation!\n「漢字",
+                        BreakKind::ProhibitedByKinsoku
+                    ),
+                    ("Hello, hyphenation!\n「漢字」", BreakKind::Allowed),
+                    ("Hello, hyphenation!\n「漢字」で", BreakKind::Allowed),
+                    (
+                        "Hello, hyphenation!\n「漢字」です",
+                        BreakKind::ProhibitedByKinsoku
+                    ),
+                    ("Hello, hyphenation!\n「漢字」です。", BreakKind::Mandatory),
+                ]
+            );
+            // the widths add up to those of the lines
+            assert_eq!(breaks[0].width + breaks[0].space_width, 7);
+            assert_eq!(breaks[1].hyphen_width, 1);
+            assert_eq!(breaks[1..4].iter().map(|v| v.width).sum::<usize>(), 12);
+            assert_eq!(breaks[4..].iter().map(|v| v.width).sum::<usize>(), 14);
+        }
+
+        // the break positions do not depend on the width
+        let positions = |max_width| {
+            TextFlow::new(text, max_width)
+                .break_opportunities()
+                .map(|v| v.position)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(positions(1), positions(80));
+    }
+
+    #[test]
+    fn test_52() {
+        // every wrap mode breaks at the opportunities of the same rules, the greedy heuristic
+        // lines of these texts included
+        for text in [
+            "」」bb》 wordbrown?，(Thequick",
+            "this is aaaa \"text word\" test",
+            "c「ー「).b，",
+            "The\"《中》 a book named 《<《「Wow》>」",
+        ] {
+            let positions = BreakOpportunities::new(text)
+                .filter(|v| v.kind != BreakKind::ProhibitedByKinsoku)
+                .map(|v| v.position)
+                .collect::<Vec<_>>();
+            for wrap_mode in [WrapMode::Greedy, WrapMode::Optimal, WrapMode::Balance] {
+                for max_width in 16..=24 {
+                    for line in TextFlow::new(text, max_width).with_wrap_mode(wrap_mode) {
+                        assert!(
+                            positions.contains(&line.position.brk),
+                            "{text:?} at {max_width} in {wrap_mode:?}: {:?}",
+                            line.position
+                        );
+                    }
+                }
+            }
+        }
+
+        let text = "f abcdefghijklmnopq, 12345678.";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 10)
+                .with_word_break(WordBreak::BreakAll)
+                .with_wrap_mode(WrapMode::Optimal)
+            =>
+            "f abcdefgh"
+            "ijklmnopq,"
+            "12345678."
+        );
+    }
+
+    #[test]
+    fn test_53() {
+        // a tab on a wrapped line advances from the start of that line
+        let text = "aaaaaa bb\tc";
+        for engine in [BreakEngine::Heuristic, BreakEngine::Uax14] {
+            for wrap_mode in [WrapMode::Greedy, WrapMode::Optimal, WrapMode::Balance] {
+                let flow = || {
+                    TextFlow::new(text, 10)
+                        .with_break_engine(engine)
+                        .with_wrap_mode(wrap_mode)
+                        .with_tab_width(8)
+                };
+                let widths = flow().map(|v| v.real_width).collect::<Vec<_>>();
+                assert_eq!(widths[1], 9, "{engine:?} {wrap_mode:?}");
+
+                let widths = flow()
+                    .with_tab_stops(TabStops::new([TabStop::right(6)]))
+                    .map(|v| v.real_width)
+                    .collect::<Vec<_>>();
+                assert_eq!(widths[1], 6, "{engine:?} {wrap_mode:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_54() {
+        // options that change nothing leave the lines of the greedy engines as they are
+        let hyphenator = Hyphenator::from_tex("");
+        for (text, max_width) in [
+            ("」」bb》 wordbrown?，(Thequick", 6),
+            (" ", 11),
+            ("！（a（\"。-    》", 14),
+            (",quick(。a  ，23", 4),
+        ] {
+            for engine in [BreakEngine::Heuristic, BreakEngine::Uax14] {
+                let flow = || TextFlow::new(text, max_width).with_break_engine(engine);
+                let lines = |flow: TextFlow| {
+                    flow.map(|v| (v.position, v.real_width, v.hyphenated))
+                        .collect::<Vec<_>>()
+                };
+                let plain = lines(flow());
+                assert_eq!(plain, lines(flow().with_hyphenator(&hyphenator)));
+                assert_eq!(
+                    plain,
+                    lines(flow().with_hanging_punctuation(HangingPunctuation::default()))
+                );
+                assert_eq!(plain, lines(flow().with_autospace(0)));
+            }
+        }
+
+        assert_lines!(" ", TextFlow::new(" ", 11) => "");
+        let text = ",quick(。a  ，23";
+        assert_lines!(
+            text,
+            TextFlow::new(text, 4).with_hyphenator(&hyphenator)
+            =>
+            ","
+            "quic"
+            "k(。"
+            "a"
+            "，23"
+        );
+    }
+
+    #[test]
+    fn test_55() {
+        // a soft hyphen after a space or at the start of a paragraph is no hyphenation point
+        for engine in [BreakEngine::Heuristic, BreakEngine::Uax14] {
+            let text = "ab \u{ad}cd efgh";
+            let lines = TextFlow::new(text, 4)
+                .with_break_engine(engine)
+                .collect::<Vec<_>>();
+            assert_eq!(lines[0].slices(text), "ab", "{engine:?}");
+            assert_eq!((lines[0].real_width, lines[0].hyphenated), (2, false));
+            assert!(lines.iter().all(|v| !v.hyphenated), "{engine:?}");
+
+            // the leading soft hyphen stays in the first line
+            let text = "\u{ad}abc def";
+            let lines = TextFlow::new(text, 4)
+                .with_break_engine(engine)
+                .collect::<Vec<_>>();
+            assert_eq!(lines[0].position.start, 0);
+            assert_eq!(lines[0].slices(text), "\u{ad}abc", "{engine:?}");
+            assert_eq!(lines[0].real_width, 3);
+            assert!(lines
+                .windows(2)
+                .all(|v| v[0].position.brk == v[1].position.start));
+            assert_eq!(lines.last().map(|v| v.position.brk), Some(text.len()));
+        }
+    }
+
+    #[test]
+    fn test_56() {
+        // trailing spaces of lines that never wrap are part of them
+        let text = "ab  \ncd ";
+        for engine in [BreakEngine::Heuristic, BreakEngine::Uax14] {
+            let lines = |white_space| {
+                TextFlow::new(text, 2)
+                    .with_break_engine(engine)
+                    .with_white_space(white_space)
+                    .map(|v| (v.position.end, v.slices(text), v.real_width))
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(lines(WhiteSpace::Pre), [(4, "ab  ", 4), (8, "cd ", 3)]);
+            assert_eq!(lines(WhiteSpace::NoWrap), [(8, text, 6)]);
+            if engine == BreakEngine::Uax14 {
+                assert_eq!(lines(WhiteSpace::PreWrap), [(2, "ab", 2), (7, "cd", 2)]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_57() {
+        // greedy heuristic lines fit the words as they come
+        let text = "an \"apple\" tree";
+        assert_lines!(text, TextFlow::new(text, 5) => "an" "\"appl" "e\"" "tree");
+        assert_lines!(text, TextFlow::new(text, 6) => "an" "\"apple" "\" tree");
+
+        // lines that would not advance or would break inside a character are fitted over
+        // the segments of their paragraph
+        let text = "\"\"》b\t）";
+        assert_lines!(text, TextFlow::new(text, 4).with_tab_width(8) => "\"\"》" "b" "\t" "）");
+        let text = " 1。quick「  a《a";
+        let lines = TextFlow::new(text, 4).collect::<Vec<_>>();
+        assert!(lines
+            .windows(2)
+            .all(|v| v[0].position.brk == v[1].position.start));
+        assert_eq!(lines.last().map(|v| v.position.brk), Some(text.len()));
+    }
+
+    #[test]
+    fn test_58() {
+        // words too long for a line are cut where the break engine allows it
+        let text = "a-b 1-2 foo-bar";
+        for engine in [BreakEngine::Heuristic, BreakEngine::Uax14] {
+            assert_lines!(
+                text,
+                TextFlow::new(text, 3)
+                    .with_break_engine(engine)
+                    .with_wrap_mode(WrapMode::Optimal)
+                =>
+                "a-b"
+                "1-2"
+                "fo"
+                "o-"
+                "bar"
+            );
+        }
+        assert_lines!(
+            text,
+            TextFlow::new(text, 3).with_break_engine(BreakEngine::Uax14)
+            =>
+            "a-b"
+            "1-2"
+            "fo"
+            "o-"
+            "bar"
+        );
+
+        // a tab cut off its segment still advances from its column
+        let text = "abcd\t）";
+        let widths = TextFlow::new(text, 4)
+            .with_wrap_mode(WrapMode::Optimal)
+            .with_tab_width(8)
+            .map(|v| v.real_width)
+            .collect::<Vec<_>>();
+        assert_eq!(widths, [4, 8, 2]);
+    }
 }